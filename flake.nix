@@ -42,8 +42,11 @@
         src = lib.fileset.toSource {
           root = ./.;
           fileset = lib.fileset.unions [
-            # ./.sqlx
+            ./.sqlx
+            ./migrations
             ./src
+            ./templates
+            ./build.rs
             ./Cargo.lock
             ./Cargo.toml
           ];
@@ -51,6 +54,11 @@
 
         cargoLock.lockFile = ./Cargo.lock;
 
+        # The integration tests need a MySQL server, run them with `cargo test` and DATABASE_URL set
+        doCheck = false;
+
+        ICONERY_GIT_HASH = self.shortRev or self.dirtyShortRev or "unknown";
+
         nativeBuildInputs = with pkgs; [pkg-config];
         buildInputs = with pkgs; [openssl];
       };
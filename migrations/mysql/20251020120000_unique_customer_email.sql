@@ -0,0 +1,21 @@
+-- Duplicated emails keep a single account: the oldest active one if any,
+-- otherwise the oldest one. Orders of the dropped duplicates are moved onto it.
+CREATE TEMPORARY TABLE customer_email_keepers AS
+SELECT email,
+       COALESCE(MIN(CASE WHEN is_active = 1 THEN id END), MIN(id)) AS keep_id
+FROM customers
+GROUP BY email;
+
+UPDATE orders o
+JOIN customers c ON c.id = o.customer_id
+JOIN customer_email_keepers k ON k.email = c.email
+SET o.customer_id = k.keep_id
+WHERE c.id <> k.keep_id;
+
+DELETE c FROM customers c
+JOIN customer_email_keepers k ON k.email = c.email
+WHERE c.id <> k.keep_id;
+
+DROP TEMPORARY TABLE customer_email_keepers;
+
+ALTER TABLE customers ADD UNIQUE INDEX customers_email_unique (email);
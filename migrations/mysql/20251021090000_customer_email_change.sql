@@ -0,0 +1,4 @@
+ALTER TABLE customers
+  ADD COLUMN pending_email VARCHAR(255) NULL,
+  ADD COLUMN email_change_token VARCHAR(64) NULL,
+  ADD UNIQUE INDEX customers_email_change_token_unique (email_change_token);
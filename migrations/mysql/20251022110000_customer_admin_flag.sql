@@ -0,0 +1,2 @@
+ALTER TABLE customers
+  ADD COLUMN is_admin BIT(1) NOT NULL DEFAULT 0;
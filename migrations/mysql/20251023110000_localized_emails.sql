@@ -0,0 +1,5 @@
+ALTER TABLE customers
+  ADD COLUMN preferred_locale VARCHAR(8) NOT NULL DEFAULT 'pt-BR';
+
+ALTER TABLE email_outbox
+  ADD COLUMN text_body MEDIUMTEXT NULL AFTER body;
@@ -0,0 +1,29 @@
+-- The initial migration used to insert demo data. It is left untouched so its checksum still
+-- matches on existing databases; the data now comes from the `seed` command instead.
+
+-- The demo customers were inserted with plaintext passwords, which can never match a login
+-- (passwords are compared hashed), so they are unmistakably seed rows. Their orders cascade.
+DELETE FROM customers
+WHERE (email, password) IN (
+  ('viniciuslmtcontato@gmail.com', 'senha_joao'),
+  ('maria.santos@example.com', 'senha_maria'),
+  ('pedro.almeida@example.com', 'senha_pedro'),
+  ('ana.costa@example.com', 'senha_ana'),
+  ('vendas@empresaabc.com', 'senha_empresa'),
+  ('lucas.pereira@example.com', 'senha_lucas')
+);
+
+-- Demo products are only removed while no remaining order refers to them
+DELETE p FROM products p
+LEFT JOIN order_items oi ON oi.product_id = p.id
+WHERE oi.id IS NULL
+  AND (p.name, p.description, p.price) IN (
+    ('Baú Aberto', 'Baú aberto', 10),
+    ('Cabeça de Dragão', 'Cabeça de dragão', 5),
+    ('Poção de Vida', 'Poção de vida', 3),
+    ('Escudo', 'Escudo', 2),
+    ('Elmo', 'Elmo', 10),
+    ('Chave', 'Chave', 9),
+    ('Bússola', 'Bússola', 1),
+    ('Duas Moedas', 'Duas moedas', 100)
+  );
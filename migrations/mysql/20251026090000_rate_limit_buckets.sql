@@ -0,0 +1,6 @@
+CREATE TABLE rate_limit_buckets (
+  bucket_key VARCHAR(255) PRIMARY KEY,
+  tokens DOUBLE NOT NULL,
+  updated_at TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+  INDEX (updated_at)
+);
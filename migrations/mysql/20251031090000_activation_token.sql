@@ -0,0 +1,5 @@
+-- Activation links carry a random token instead of the hash of the email. Accounts still waiting
+-- for activation get a link with one by signing up again
+ALTER TABLE customers
+  ADD COLUMN activation_token VARCHAR(64) NULL,
+  ADD UNIQUE INDEX customers_activation_token_unique (activation_token);
@@ -0,0 +1,5 @@
+-- Password reset links carry a random token, valid for a while, instead of the hash of the email
+ALTER TABLE customers
+  ADD COLUMN password_reset_token VARCHAR(64) NULL,
+  ADD COLUMN password_reset_expires_at DATETIME NULL,
+  ADD UNIQUE INDEX customers_password_reset_token_unique (password_reset_token);
@@ -0,0 +1,60 @@
+-- Same schema as the MySQL migrations up to 20251026090000_rate_limit_buckets
+CREATE TABLE products (
+  id BIGSERIAL PRIMARY KEY,
+  name VARCHAR(255) NOT NULL,
+  description TEXT NULL,
+  price BIGINT NOT NULL,
+  is_featured BOOLEAN NOT NULL
+);
+
+CREATE TABLE customers (
+  id BIGSERIAL PRIMARY KEY,
+  name VARCHAR(255) NOT NULL,
+  email VARCHAR(255) NOT NULL UNIQUE,
+  password VARCHAR(255) NOT NULL,
+  phone_number VARCHAR(255),
+  address VARCHAR(255),
+  is_active BOOLEAN NOT NULL,
+  pending_email VARCHAR(255) NULL,
+  email_change_token VARCHAR(64) NULL UNIQUE,
+  preferred_locale VARCHAR(8) NOT NULL DEFAULT 'pt-BR',
+  is_admin BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE orders (
+  id BIGSERIAL PRIMARY KEY,
+  customer_id BIGINT NOT NULL REFERENCES customers(id) ON DELETE CASCADE
+);
+
+CREATE TABLE order_items (
+  id BIGSERIAL PRIMARY KEY,
+  order_id BIGINT NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+  product_id BIGINT NOT NULL REFERENCES products(id),
+  amount BIGINT NOT NULL
+);
+
+CREATE INDEX order_items_order_id ON order_items (order_id);
+
+CREATE TABLE email_outbox (
+  id BIGSERIAL PRIMARY KEY,
+  recipient VARCHAR(255) NOT NULL,
+  subject VARCHAR(255) NOT NULL,
+  body TEXT NOT NULL,
+  text_body TEXT NULL,
+  status VARCHAR(16) NOT NULL DEFAULT 'pending',
+  attempts INT NOT NULL DEFAULT 0,
+  last_error TEXT NULL,
+  next_attempt_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP,
+  sent_at TIMESTAMP NULL
+);
+
+CREATE INDEX email_outbox_status_next_attempt_at ON email_outbox (status, next_attempt_at);
+
+CREATE TABLE rate_limit_buckets (
+  bucket_key VARCHAR(255) PRIMARY KEY,
+  tokens DOUBLE PRECISION NOT NULL,
+  updated_at TIMESTAMP(3) NOT NULL DEFAULT LOCALTIMESTAMP(3)
+);
+
+CREATE INDEX rate_limit_buckets_updated_at ON rate_limit_buckets (updated_at);
@@ -0,0 +1,4 @@
+-- Emails are unique and looked up regardless of case, as under the MySQL collation
+CREATE EXTENSION IF NOT EXISTS citext;
+ALTER TABLE customers ALTER COLUMN email TYPE CITEXT;
+ALTER TABLE customers ALTER COLUMN pending_email TYPE CITEXT;
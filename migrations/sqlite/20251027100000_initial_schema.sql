@@ -0,0 +1,61 @@
+-- Same schema as the MySQL migrations up to 20251026090000_rate_limit_buckets. Timestamps are
+-- stored as UTC text, as written by CURRENT_TIMESTAMP and datetime('now').
+CREATE TABLE products (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name VARCHAR(255) NOT NULL,
+  description TEXT NULL,
+  price BIGINT NOT NULL,
+  is_featured BOOLEAN NOT NULL
+);
+
+CREATE TABLE customers (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name VARCHAR(255) NOT NULL,
+  email VARCHAR(255) NOT NULL COLLATE NOCASE UNIQUE,
+  password VARCHAR(255) NOT NULL,
+  phone_number VARCHAR(255),
+  address VARCHAR(255),
+  is_active BOOLEAN NOT NULL,
+  pending_email VARCHAR(255) NULL COLLATE NOCASE,
+  email_change_token VARCHAR(64) NULL UNIQUE,
+  preferred_locale VARCHAR(8) NOT NULL DEFAULT 'pt-BR',
+  is_admin BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE orders (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  customer_id BIGINT NOT NULL REFERENCES customers(id) ON DELETE CASCADE
+);
+
+CREATE TABLE order_items (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  order_id BIGINT NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+  product_id BIGINT NOT NULL REFERENCES products(id),
+  amount BIGINT NOT NULL
+);
+
+CREATE INDEX order_items_order_id ON order_items (order_id);
+
+CREATE TABLE email_outbox (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  recipient VARCHAR(255) NOT NULL,
+  subject VARCHAR(255) NOT NULL,
+  body TEXT NOT NULL,
+  text_body TEXT NULL,
+  status VARCHAR(16) NOT NULL DEFAULT 'pending',
+  attempts INT NOT NULL DEFAULT 0,
+  last_error TEXT NULL,
+  next_attempt_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  sent_at DATETIME NULL
+);
+
+CREATE INDEX email_outbox_status_next_attempt_at ON email_outbox (status, next_attempt_at);
+
+CREATE TABLE rate_limit_buckets (
+  bucket_key VARCHAR(255) PRIMARY KEY,
+  tokens DOUBLE NOT NULL,
+  updated_at DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
+
+CREATE INDEX rate_limit_buckets_updated_at ON rate_limit_buckets (updated_at);
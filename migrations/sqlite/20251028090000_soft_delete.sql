@@ -0,0 +1,5 @@
+ALTER TABLE products
+  ADD COLUMN deleted_at DATETIME NULL;
+
+ALTER TABLE customers
+  ADD COLUMN deleted_at DATETIME NULL;
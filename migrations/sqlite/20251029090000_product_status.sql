@@ -0,0 +1,5 @@
+-- The default keeps the products created so far live. New ones start as drafts because the API
+-- always inserts a status, draft unless told otherwise
+ALTER TABLE products ADD COLUMN status VARCHAR(16) NOT NULL DEFAULT 'published';
+ALTER TABLE products ADD COLUMN publish_at DATETIME NULL;
+ALTER TABLE products ADD COLUMN unpublish_at DATETIME NULL;
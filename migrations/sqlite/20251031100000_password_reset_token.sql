@@ -0,0 +1,4 @@
+-- Password reset links carry a random token, valid for a while, instead of the hash of the email
+ALTER TABLE customers ADD COLUMN password_reset_token VARCHAR(64) NULL;
+ALTER TABLE customers ADD COLUMN password_reset_expires_at DATETIME NULL;
+CREATE UNIQUE INDEX customers_password_reset_token_unique ON customers (password_reset_token);
@@ -0,0 +1,78 @@
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::{Next, from_fn},
+    web::{self, Data, ServiceConfig},
+};
+use base64::{Engine, prelude::BASE64_STANDARD};
+
+use crate::{
+    AppData, Result, customer, db::with_pool, error::Error, outbox, product,
+    repository::CustomerRepo, retention,
+};
+
+/// Lets the request through only with the HTTP Basic credentials of an active admin, as made by
+/// the `create-admin` command.
+pub async fn require_admin(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> std::result::Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let maybe_data = request.app_data::<Data<AppData>>().cloned();
+
+    let maybe_admin_id = match (basic_credentials(&request), maybe_data) {
+        (Some((email, password)), Some(data)) => find_admin(&data, &email, &password).await?,
+        _ => None,
+    };
+
+    match maybe_admin_id {
+        Some(admin_id) => {
+            tracing::info!(admin_id, "admin request");
+            Ok(next.call(request).await?.map_into_left_body())
+        }
+        None => {
+            tracing::warn!("admin credentials missing or wrong");
+            Ok(request
+                .error_response(Error::UnauthorizedError)
+                .map_into_right_body())
+        }
+    }
+}
+
+/// Email and password from an `Authorization: Basic` header.
+fn basic_credentials(request: &ServiceRequest) -> Option<(String, String)> {
+    let value = request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?;
+    let decoded = BASE64_STANDARD.decode(value.strip_prefix("Basic ")?).ok()?;
+    let (email, password) = std::str::from_utf8(&decoded).ok()?.split_once(':')?;
+
+    Some((email.to_owned(), password.to_owned()))
+}
+
+async fn find_admin(data: &AppData, email: &str, password: &str) -> Result<Option<i64>> {
+    let hashed_password = crate::util::hash(password);
+
+    let maybe_admin_id = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .find_admin_id_by_login(email, &hashed_password)
+            .await?
+    });
+
+    Ok(maybe_admin_id)
+}
+
+/// Routes under `/admin`, all behind [`require_admin`].
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::scope("/admin")
+            .wrap(from_fn(require_admin))
+            .configure(customer::configure_admin)
+            .configure(outbox::configure)
+            .configure(product::configure_admin)
+            .configure(retention::configure),
+    );
+}
@@ -0,0 +1,145 @@
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+
+use crate::{
+    Config,
+    db::{DbClient, DbPool, with_pool},
+    mailer::Email,
+    repository::CustomerRepo,
+    seed::SeedProfile,
+};
+
+#[derive(Parser)]
+#[command(version, about = "Iconery shop API")]
+pub struct Cli {
+    /// JSON config file, `./config.json` is used when present otherwise
+    #[arg(long, global = true, env = "ICONERY_CONFIG")]
+    pub config: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the HTTP server (default)
+    Serve,
+    /// Apply the pending database migrations
+    Migrate,
+    /// Insert sample data, leaving rows that already exist alone
+    Seed {
+        #[arg(long, value_enum, default_value_t = SeedProfile::Demo)]
+        profile: SeedProfile,
+    },
+    /// Create an admin account for the `/admin` routes, or promote an existing account and reset
+    /// its password
+    CreateAdmin {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        email: String,
+        #[arg(long, env = "ICONERY_ADMIN_PASSWORD", hide_env_values = true)]
+        password: String,
+    },
+    /// Send an email through the configured mail transport
+    SendTestEmail {
+        #[arg(long)]
+        to: String,
+    },
+    /// Validate the configuration and exit
+    CheckConfig,
+}
+
+pub async fn connect_db(config: &Config) -> anyhow::Result<DbPool> {
+    DbPool::connect(config)
+        .await
+        .context("Failed to connect to the DB")
+}
+
+pub async fn migrate(config: &Config) -> anyhow::Result<()> {
+    let db_pool = connect_db(config).await?;
+
+    db_pool
+        .migrate()
+        .await
+        .context("Failed to run the migrations")?;
+
+    println!("Migrations are up to date");
+
+    Ok(())
+}
+
+pub async fn seed(config: &Config, profile: SeedProfile) -> anyhow::Result<()> {
+    let db_pool = connect_db(config).await?;
+
+    crate::seed::run(&db_pool, profile)
+        .await
+        .context("Failed to seed the DB")?;
+
+    println!("Seed data is in place");
+
+    Ok(())
+}
+
+pub async fn create_admin(
+    config: &Config,
+    name: &str,
+    email: &str,
+    password: &str,
+) -> anyhow::Result<()> {
+    let db_pool = connect_db(config).await?;
+    let hashed_password = crate::util::hash(password);
+
+    with_pool!(&db_pool, |pool| {
+        let mut connection = pool
+            .acquire()
+            .await
+            .context("Failed to connect to the DB")?;
+
+        connection
+            .upsert_admin(name, email, &hashed_password)
+            .await
+            .context("Failed to create the admin")?;
+    });
+
+    println!("Admin {email} is ready, use it as HTTP Basic credentials on the /admin routes");
+
+    Ok(())
+}
+
+pub fn send_test_email(config: &Config, to: &str) -> anyhow::Result<()> {
+    let mailer =
+        crate::mailer::from_config(config).context("Failed to set up the mail transport")?;
+
+    mailer
+        .send(&Email {
+            to: to.to_owned(),
+            subject: "Iconery test email".to_owned(),
+            html_body: "<p>The mail transport is working.</p>".to_owned(),
+            text_body: "The mail transport is working.".to_owned(),
+        })
+        .context("Failed to send the test email")?;
+
+    println!("Test email sent to {to}");
+
+    Ok(())
+}
+
+pub fn check_config(config: &Config) {
+    println!("Configuration is valid");
+    println!("server: {}:{}", config.server_host, config.server_port);
+    match &config.database_url {
+        Some(_) => println!("database: {:?} from database_url", config.db_client),
+        None if config.db_client == DbClient::Sqlite => {
+            println!("database: sqlite://{}", config.db_name)
+        }
+        None => println!(
+            "database: {}://{}@{}:{}/{}",
+            config.db_client.schemes()[0],
+            config.db_user,
+            config.db_host,
+            config.db_port,
+            config.db_name
+        ),
+    }
+    println!("mail transport: {:?}", config.mail_transport);
+}
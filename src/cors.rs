@@ -0,0 +1,193 @@
+use actix_web::{
+    HttpResponse,
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        Method,
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+    },
+    middleware::Next,
+    web::Data,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{AppData, Config};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    /// Full origins such as `https://shop.example.com`, or `*` for any. Empty disables CORS.
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub exposed_headers: Vec<String>,
+    pub allow_credentials: bool,
+    pub max_age_secs: u64,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE"]
+                .map(str::to_owned)
+                .to_vec(),
+            allowed_headers: ["authorization", "content-type", "x-request-id"]
+                .map(str::to_owned)
+                .to_vec(),
+            exposed_headers: vec!["x-request-id".to_owned()],
+            allow_credentials: false,
+            max_age_secs: 3600,
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn validate(&self, name: &str, problems: &mut Vec<String>) {
+        for origin in &self.allowed_origins {
+            if origin != "*" && !origin.starts_with("http://") && !origin.starts_with("https://") {
+                problems.push(format!(
+                    "{name}.allowed_origins must be * or start with http:// or https://, got {origin:?}"
+                ));
+            }
+        }
+
+        for method in &self.allowed_methods {
+            if method.parse::<Method>().is_err() {
+                problems.push(format!(
+                    "{name}.allowed_methods has an invalid method {method:?}"
+                ));
+            }
+        }
+
+        for header in self.allowed_headers.iter().chain(&self.exposed_headers) {
+            if header.parse::<HeaderName>().is_err() {
+                problems.push(format!("{name} has an invalid header name {header:?}"));
+            }
+        }
+    }
+
+    fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.trim_end_matches('/') == origin)
+    }
+
+    fn allows_method(&self, method: &str) -> bool {
+        self.allowed_methods
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(method))
+    }
+
+    fn allows_headers(&self, requested: &str) -> bool {
+        requested
+            .split(',')
+            .map(str::trim)
+            .filter(|header| !header.is_empty())
+            .all(|header| {
+                self.allowed_headers
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(header))
+            })
+    }
+
+    fn insert_origin_headers(&self, headers: &mut HeaderMap, origin: &HeaderValue) {
+        // Credentialed requests can't use the wildcard, so the origin is always echoed back
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+        headers.append(header::VARY, HeaderValue::from_static("Origin"));
+
+        if self.allow_credentials {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+    }
+}
+
+/// Settings for `path`: the override with the longest matching prefix, or the global ones.
+fn settings_for<'c>(config: &'c Config, path: &str) -> &'c CorsConfig {
+    config
+        .cors_overrides
+        .iter()
+        .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map_or(&config.cors, |(_, settings)| settings)
+}
+
+/// Answers preflight requests and adds the CORS headers to responses for allowed origins.
+/// Requests from other origins go through untouched, so the browser blocks them.
+pub async fn handle_cors(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let Some(data) = request.app_data::<Data<AppData>>().cloned() else {
+        return Ok(next.call(request).await?.map_into_left_body());
+    };
+    let settings = settings_for(
+        &data.config,
+        &crate::versioning::unversioned_path(request.path()),
+    );
+
+    let Some(origin) = request
+        .headers()
+        .get(header::ORIGIN)
+        .filter(|origin| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| settings.allows_origin(origin))
+        })
+        .cloned()
+    else {
+        return Ok(next.call(request).await?.map_into_left_body());
+    };
+
+    let requested_method = request
+        .headers()
+        .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+
+    if let (&Method::OPTIONS, Some(requested_method)) = (request.method(), requested_method) {
+        let requested_headers = request
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+
+        if !settings.allows_method(&requested_method) || !settings.allows_headers(requested_headers)
+        {
+            return Ok(request
+                .into_response(HttpResponse::Forbidden().finish())
+                .map_into_right_body());
+        }
+
+        let mut response = HttpResponse::NoContent()
+            .insert_header((
+                header::ACCESS_CONTROL_ALLOW_METHODS,
+                settings.allowed_methods.join(", "),
+            ))
+            .insert_header((
+                header::ACCESS_CONTROL_ALLOW_HEADERS,
+                settings.allowed_headers.join(", "),
+            ))
+            .insert_header((header::ACCESS_CONTROL_MAX_AGE, settings.max_age_secs))
+            .finish();
+        settings.insert_origin_headers(response.headers_mut(), &origin);
+
+        return Ok(request.into_response(response).map_into_right_body());
+    }
+
+    let mut response = next.call(request).await?;
+    settings.insert_origin_headers(response.headers_mut(), &origin);
+
+    if !settings.exposed_headers.is_empty()
+        && let Ok(exposed_headers) = HeaderValue::from_str(&settings.exposed_headers.join(", "))
+    {
+        response
+            .headers_mut()
+            .insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, exposed_headers);
+    }
+
+    Ok(response.map_into_left_body())
+}
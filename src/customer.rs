@@ -1,13 +1,20 @@
 use actix_web::{
     HttpResponse,
-    web::{Data, Json, Path},
+    web::{Data, Json, Path, ServiceConfig},
 };
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
 
-use crate::{AppData, Result};
+use crate::{
+    AppData, Result,
+    db::with_pool,
+    repository::CustomerRepo,
+    service::{self, customer::SignUp},
+    templates::Locale,
+};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CustomerRequest {
     pub name: String,
     pub email: String,
@@ -15,9 +22,35 @@ pub struct CustomerRequest {
     pub phone_number: Option<String>,
     pub address: Option<String>,
     pub is_active: Option<bool>,
+    pub preferred_locale: Option<Locale>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CustomerPatchRequest {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    #[serde(default, deserialize_with = "crate::util::deserialize_some")]
+    #[schema(value_type = Option<String>)]
+    pub phone_number: Option<Option<String>>,
+    #[serde(default, deserialize_with = "crate::util::deserialize_some")]
+    #[schema(value_type = Option<String>)]
+    pub address: Option<Option<String>>,
+    pub is_active: Option<bool>,
+    pub preferred_locale: Option<Locale>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CustomerPasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CustomerEmailChangeRequest {
+    pub email: String,
 }
 
-#[derive(FromRow, Serialize)]
+#[derive(FromRow, Serialize, ToSchema)]
 pub struct CustomerResponse {
     pub id: i64,
     pub name: String,
@@ -26,203 +59,603 @@ pub struct CustomerResponse {
     pub phone_number: Option<String>,
     pub address: Option<String>,
     pub is_active: bool,
+    pub preferred_locale: String,
 }
 
-#[derive(Deserialize)]
+/// [`CustomerResponse`] without the password hash, returned from v2 on.
+#[derive(FromRow, Serialize, ToSchema)]
+pub struct CustomerV2Response {
+    pub id: i64,
+    pub name: String,
+    pub email: String,
+    pub phone_number: Option<String>,
+    pub address: Option<String>,
+    pub is_active: bool,
+    pub preferred_locale: String,
+}
+
+impl From<CustomerResponse> for CustomerV2Response {
+    fn from(customer: CustomerResponse) -> Self {
+        Self {
+            id: customer.id,
+            name: customer.name,
+            email: customer.email,
+            phone_number: customer.phone_number,
+            address: customer.address,
+            is_active: customer.is_active,
+            preferred_locale: customer.preferred_locale,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct CustomerLoginRequest {
     pub email: String,
     pub password: String,
 }
 
-#[actix_web::post("/api/customer")]
+#[utoipa::path(
+    post,
+    path = "/api/v1/customer",
+    tag = "customer",
+    request_body = CustomerRequest,
+    responses(
+        (status = 200, description = "Customer created and activation email queued, or the email re-sent to a pending account"),
+        (status = 409, description = "The email is already in use", body = String, content_type = "text/plain"),
+        (status = 429, description = "Too many requests", body = String, content_type = "text/plain", headers(("Retry-After" = u64, description = "Seconds to wait before retrying"))),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::post("/customer")]
+#[tracing::instrument(skip_all, fields(customer_id = tracing::field::Empty, email = %crate::logging::redact_email(&body.email)))]
 pub async fn create_customer(
     data: Data<AppData>,
     body: Json<CustomerRequest>,
 ) -> Result<HttpResponse> {
-    let hashed_password = crate::util::hash(&body.password);
-
-    sqlx::query!(
-        r#"INSERT INTO customers (name, email, password, phone_number, address, is_active)
-           VALUES (?, ?, ?, ?, ?, ?)"#,
-        body.name,
-        body.email,
-        hashed_password,
-        body.phone_number,
-        body.address,
-        body.is_active.unwrap_or(false),
-    )
-    .execute(&data.db_pool)
-    .await?;
-
-    let confirm_link = format!(
-        "http://{}:{}/api/customer/activate/{}",
-        data.config.server_host,
-        data.config.server_port,
-        crate::util::hash(&body.email)
-    );
-    let message_body = format!(
-        "<b>Email de confirmação de cadastro</b><br><br>\
-         Seja bem-vindo(a), {}! Clique no link abaixo para confirmar seu cadastro.<br><br>\
-         <a href='{}'>Clique aqui</a>",
-        body.name, confirm_link
-    );
-
-    crate::util::send_html_email(
-        &data.config,
-        &body.email,
-        "Confirmação de novo cadastro",
-        message_body,
-    )?;
+    let sign_up = with_pool!(&data.db_pool, |pool| {
+        let mut transaction = pool.begin().await?;
+        let sign_up = service::customer::sign_up(&mut *transaction, &data, &body).await?;
+        transaction.commit().await?;
+        sign_up
+    });
+
+    if let SignUp::Created(customer_id) = sign_up {
+        tracing::Span::current().record("customer_id", customer_id);
+        data.metrics.customer_signed_up();
+    }
 
     Ok(HttpResponse::Ok().finish())
 }
 
-#[actix_web::put("/api/customer/{id:\\d+}")]
+#[utoipa::path(
+    put,
+    path = "/api/v1/customer/{id}",
+    tag = "customer",
+    params(("id" = i64, Path, description = "Customer id")),
+    request_body = CustomerRequest,
+    responses(
+        (status = 200, description = "Customer replaced; a new email must be confirmed before it applies"),
+        (status = 404, description = "Not found"),
+        (status = 409, description = "The email is already in use", body = String, content_type = "text/plain"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::put("/customer/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(customer_id = %path))]
 pub async fn update_customer(
     path: Path<i64>,
     data: Data<AppData>,
     body: Json<CustomerRequest>,
 ) -> Result<HttpResponse> {
-    let hashed_password = crate::util::hash(&body.password);
-
-    sqlx::query!(
-        r#"UPDATE customers
-           SET name=?, email=?, password=?, phone_number=?, address=?, is_active=?
-           WHERE id=?"#,
-        body.name,
-        body.email,
-        hashed_password,
-        body.phone_number,
-        body.address,
-        body.is_active.unwrap_or(false),
-        path.into_inner()
-    )
-    .execute(&data.db_pool)
-    .await?;
+    with_pool!(&data.db_pool, |pool| {
+        let mut transaction = pool.begin().await?;
+        service::customer::update_customer(&mut *transaction, &data, path.into_inner(), &body)
+            .await?;
+        transaction.commit().await?;
+    });
 
     Ok(HttpResponse::Ok().finish())
 }
 
-#[actix_web::delete("/api/customer/{id:\\d+}")]
-pub async fn delete_customer(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
-    sqlx::query!("DELETE FROM customers WHERE id=?", path.into_inner())
-        .execute(&data.db_pool)
+#[utoipa::path(
+    patch,
+    path = "/api/v1/customer/{id}",
+    tag = "customer",
+    params(("id" = i64, Path, description = "Customer id")),
+    request_body = CustomerPatchRequest,
+    responses(
+        (status = 200, description = "Customer updated; a new email must be confirmed before it applies"),
+        (status = 404, description = "Not found"),
+        (status = 409, description = "The email is already in use", body = String, content_type = "text/plain"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::patch("/customer/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(customer_id = %path))]
+pub async fn patch_customer(
+    path: Path<i64>,
+    data: Data<AppData>,
+    body: Json<CustomerPatchRequest>,
+) -> Result<HttpResponse> {
+    with_pool!(&data.db_pool, |pool| {
+        let mut transaction = pool.begin().await?;
+        service::customer::patch_customer(&mut *transaction, &data, path.into_inner(), &body)
+            .await?;
+        transaction.commit().await?;
+    });
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/customer/{id}/password",
+    tag = "customer",
+    params(("id" = i64, Path, description = "Customer id")),
+    request_body = CustomerPasswordRequest,
+    responses(
+        (status = 200, description = "Password changed", body = String, content_type = "text/plain"),
+        (status = 403, description = "Wrong current password", body = String, content_type = "text/plain"),
+        (status = 404, description = "Not found"),
+        (status = 429, description = "Too many requests", body = String, content_type = "text/plain", headers(("Retry-After" = u64, description = "Seconds to wait before retrying"))),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::put("/customer/{id:\\d+}/password")]
+#[tracing::instrument(skip_all, fields(customer_id = %path))]
+pub async fn change_customer_password(
+    path: Path<i64>,
+    data: Data<AppData>,
+    body: Json<CustomerPasswordRequest>,
+) -> Result<HttpResponse> {
+    let changed = with_pool!(&data.db_pool, |pool| {
+        service::customer::change_password(
+            &mut *pool.acquire().await?,
+            path.into_inner(),
+            &body.current_password,
+            &body.new_password,
+        )
+        .await?
+    });
+
+    if changed {
+        Ok(HttpResponse::Ok().body("Senha alterada com sucesso"))
+    } else {
+        Ok(HttpResponse::Forbidden().body("Senha atual incorreta"))
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/customer/{id}/email",
+    tag = "customer",
+    params(("id" = i64, Path, description = "Customer id")),
+    request_body = CustomerEmailChangeRequest,
+    responses(
+        (status = 200, description = "Confirmation sent to the new email"),
+        (status = 404, description = "Not found"),
+        (status = 409, description = "The email is already in use", body = String, content_type = "text/plain"),
+        (status = 429, description = "Too many requests", body = String, content_type = "text/plain", headers(("Retry-After" = u64, description = "Seconds to wait before retrying"))),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::post("/customer/{id:\\d+}/email")]
+#[tracing::instrument(skip_all, fields(customer_id = %path))]
+pub async fn request_email_change(
+    path: Path<i64>,
+    data: Data<AppData>,
+    body: Json<CustomerEmailChangeRequest>,
+) -> Result<HttpResponse> {
+    with_pool!(&data.db_pool, |pool| {
+        let mut transaction = pool.begin().await?;
+        service::customer::start_email_change(
+            &mut *transaction,
+            &data,
+            path.into_inner(),
+            &body.email,
+        )
         .await?;
+        transaction.commit().await?;
+    });
 
     Ok(HttpResponse::Ok().finish())
 }
 
-#[actix_web::get("/api/customer/{id:\\d+}")]
-pub async fn get_customer(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
-    let customer = sqlx::query_as!(
-        CustomerResponse,
-        r#"SELECT id, name, email, password, phone_number, address, is_active as `is_active: _`
-           FROM customers WHERE id=?"#,
-        path.into_inner()
+#[utoipa::path(
+    delete,
+    path = "/api/v1/customer/{id}/email",
+    tag = "customer",
+    params(("id" = i64, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "Pending email change cancelled"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::delete("/customer/{id:\\d+}/email")]
+#[tracing::instrument(skip_all, fields(customer_id = %path))]
+pub async fn cancel_email_change(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
+    with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .cancel_email_change(path.into_inner())
+            .await?
+    });
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/customer/confirm-email/{token}",
+    tag = "customer",
+    params(("token" = String, Path, description = "Token from the confirmation email")),
+    responses(
+        (status = 200, description = "Email changed", body = String, content_type = "text/plain"),
+        (status = 404, description = "Invalid token", body = String, content_type = "text/plain"),
+        (status = 409, description = "The email is already in use", body = String, content_type = "text/plain"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .fetch_one(&data.db_pool)
-    .await?;
+)]
+#[actix_web::get("/customer/confirm-email/{token}")]
+#[tracing::instrument(skip_all)]
+pub async fn confirm_email_change(path: Path<String>, data: Data<AppData>) -> Result<HttpResponse> {
+    let changed = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .confirm_email_change(&path.into_inner())
+            .await?
+    });
+
+    if changed {
+        Ok(HttpResponse::Ok().body("Email alterado com sucesso"))
+    } else {
+        Ok(HttpResponse::NotFound().body("Token inválido"))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/customer/cancel-email/{token}",
+    tag = "customer",
+    params(("token" = String, Path, description = "Token from the notice sent to the current email")),
+    responses(
+        (status = 200, description = "Email change cancelled", body = String, content_type = "text/plain"),
+        (status = 404, description = "Invalid token", body = String, content_type = "text/plain"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::get("/customer/cancel-email/{token}")]
+#[tracing::instrument(skip_all)]
+pub async fn cancel_email_change_with_token(
+    path: Path<String>,
+    data: Data<AppData>,
+) -> Result<HttpResponse> {
+    let cancelled = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .cancel_email_change_with_token(&path.into_inner())
+            .await?
+    });
+
+    if cancelled {
+        Ok(HttpResponse::Ok().body("Alteração de email cancelada"))
+    } else {
+        Ok(HttpResponse::NotFound().body("Token inválido"))
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/customer/{id}",
+    tag = "customer",
+    params(("id" = i64, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "Customer hidden until restored or purged, orders kept"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::delete("/customer/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(customer_id = %path))]
+pub async fn delete_customer(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
+    with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .delete_customer(path.into_inner())
+            .await?
+    });
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/customer/{id}/restore",
+    tag = "admin",
+    params(("id" = i64, Path, description = "Customer id")),
+    security(("admin_auth" = [])),
+    responses(
+        (status = 200, description = "Customer restored"),
+        (status = 401, description = "Admin credentials missing or wrong", body = String, content_type = "text/plain"),
+        (status = 404, description = "Not found or not deleted"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::post("/customer/{id:\\d+}/restore")]
+#[tracing::instrument(skip_all, fields(customer_id = %path))]
+pub async fn restore_customer(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
+    let restored = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .restore_customer(path.into_inner())
+            .await?
+    });
+
+    if restored {
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/customer/{id}",
+    tag = "customer",
+    params(("id" = i64, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "The customer", body = CustomerResponse),
+        (status = 404, description = "Not found"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::get("/customer/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(customer_id = %path))]
+pub async fn get_customer(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
+    let customer = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .get_customer(path.into_inner())
+            .await?
+    });
 
     Ok(HttpResponse::Ok().json(customer))
 }
 
-#[actix_web::get("/api/customer")]
-pub async fn get_customers(data: Data<AppData>) -> Result<HttpResponse> {
-    let customers = sqlx::query_as!(
-        CustomerResponse,
-        r#"SELECT id, name, email, password, phone_number, address, is_active as `is_active: _`
-           FROM customers"#
+#[utoipa::path(
+    get,
+    path = "/api/v1/customer",
+    tag = "customer",
+    responses(
+        (status = 200, description = "All customers", body = Vec<CustomerResponse>),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .fetch_all(&data.db_pool)
-    .await?;
+)]
+#[actix_web::get("/customer")]
+#[tracing::instrument(skip_all)]
+pub async fn get_customers(data: Data<AppData>) -> Result<HttpResponse> {
+    let customers = with_pool!(&data.db_pool, |pool| {
+        pool.acquire().await?.get_customers().await?
+    });
 
     Ok(HttpResponse::Ok().json(customers))
 }
 
-#[actix_web::get("/api/customer/login")]
+#[utoipa::path(
+    get,
+    path = "/api/v1/customer/login",
+    tag = "customer",
+    request_body = CustomerLoginRequest,
+    responses(
+        (status = 200, description = "The logged in customer", body = CustomerResponse),
+        (status = 404, description = "Wrong email or password, or inactive account"),
+        (status = 429, description = "Too many requests", body = String, content_type = "text/plain", headers(("Retry-After" = u64, description = "Seconds to wait before retrying"))),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::get("/customer/login")]
+#[tracing::instrument(skip_all, fields(email = %crate::logging::redact_email(&body.email)))]
 pub async fn login_customer(
     data: Data<AppData>,
     body: Json<CustomerLoginRequest>,
 ) -> Result<HttpResponse> {
-    let hashed_password = crate::util::hash(&body.password);
+    let maybe_customer = with_pool!(&data.db_pool, |pool| {
+        service::customer::log_in(&mut *pool.acquire().await?, &body.email, &body.password).await?
+    });
+
+    match maybe_customer {
+        Some(customer) => Ok(HttpResponse::Ok().json(customer)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
 
-    let maybe_customer = sqlx::query_as!(
-        CustomerResponse,
-        r#"SELECT id, name, email, password, phone_number, address, is_active as `is_active: _`
-        FROM customers
-        WHERE email=? AND password=? AND is_active=TRUE"#,
-        body.email,
-        hashed_password
+#[utoipa::path(
+    post,
+    path = "/api/v2/customer/login",
+    tag = "customer",
+    request_body = CustomerLoginRequest,
+    responses(
+        (status = 200, description = "The logged in customer", body = CustomerV2Response),
+        (status = 404, description = "Wrong email or password, or inactive account"),
+        (status = 429, description = "Too many requests", body = String, content_type = "text/plain", headers(("Retry-After" = u64, description = "Seconds to wait before retrying"))),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .fetch_optional(&data.db_pool)
-    .await?;
+)]
+#[actix_web::post("/customer/login")]
+#[tracing::instrument(skip_all, fields(email = %crate::logging::redact_email(&body.email)))]
+pub async fn login_customer_v2(
+    data: Data<AppData>,
+    body: Json<CustomerLoginRequest>,
+) -> Result<HttpResponse> {
+    let maybe_customer = with_pool!(&data.db_pool, |pool| {
+        service::customer::log_in(&mut *pool.acquire().await?, &body.email, &body.password).await?
+    });
 
     match maybe_customer {
-        Some(customer) => Ok(HttpResponse::Ok().json(customer)),
+        Some(customer) => Ok(HttpResponse::Ok().json(CustomerV2Response::from(customer))),
         None => Ok(HttpResponse::NotFound().finish()),
     }
 }
 
-#[actix_web::get("/api/customer/activate/{token}")]
-pub async fn activate_customer(path: Path<String>, data: Data<AppData>) -> Result<HttpResponse> {
-    let query_result = sqlx::query!(
-        "UPDATE customers SET is_active=TRUE WHERE MD5(email)=?",
-        path.into_inner()
+#[utoipa::path(
+    get,
+    path = "/api/v2/customer/{id}",
+    tag = "customer",
+    params(("id" = i64, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "The customer", body = CustomerV2Response),
+        (status = 404, description = "Not found"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .execute(&data.db_pool)
-    .await?;
+)]
+#[actix_web::get("/customer/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(customer_id = %path))]
+pub async fn get_customer_v2(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
+    let customer = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .get_customer(path.into_inner())
+            .await?
+    });
+
+    Ok(HttpResponse::Ok().json(CustomerV2Response::from(customer)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/customer",
+    tag = "customer",
+    responses(
+        (status = 200, description = "All customers", body = Vec<CustomerV2Response>),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::get("/customer")]
+#[tracing::instrument(skip_all)]
+pub async fn get_customers_v2(data: Data<AppData>) -> Result<HttpResponse> {
+    let customers = with_pool!(&data.db_pool, |pool| {
+        pool.acquire().await?.get_customers().await?
+    });
+
+    Ok(HttpResponse::Ok().json(
+        customers
+            .into_iter()
+            .map(CustomerV2Response::from)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/customer/activate/{token}",
+    tag = "customer",
+    params(("token" = String, Path, description = "Token from the activation email")),
+    responses(
+        (status = 200, description = "Customer activated", body = String, content_type = "text/plain"),
+        (status = 404, description = "Not found"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::get("/customer/activate/{token}")]
+#[tracing::instrument(skip_all)]
+pub async fn activate_customer(path: Path<String>, data: Data<AppData>) -> Result<HttpResponse> {
+    let activated = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .activate_customer(&path.into_inner())
+            .await?
+    });
 
-    if query_result.rows_affected() > 0 {
+    if activated {
+        data.metrics.customer_activated();
         Ok(HttpResponse::Ok().body("Cliente ativado!"))
     } else {
         Ok(HttpResponse::NotFound().finish())
     }
 }
 
-#[actix_web::get("/api/customer/reset-password/{email}")]
+#[utoipa::path(
+    get,
+    path = "/api/v1/customer/reset-password/{email}",
+    tag = "customer",
+    params(("email" = String, Path, description = "Email of the account")),
+    responses(
+        (status = 200, description = "Reset email queued"),
+        (status = 429, description = "Too many requests", body = String, content_type = "text/plain", headers(("Retry-After" = u64, description = "Seconds to wait before retrying"))),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::get("/customer/reset-password/{email}")]
+#[tracing::instrument(skip_all, fields(email = %crate::logging::redact_email(&path)))]
 pub async fn send_password_reset(path: Path<String>, data: Data<AppData>) -> Result<HttpResponse> {
-    let email = path.into_inner();
-
-    let reset_link = format!(
-        "https://{}:{}/reset-password-form?token={}",
-        data.config.server_host,
-        data.config.server_port,
-        crate::util::hash(&email)
-    );
-
-    let message_body = format!(
-        "<p>Você solicitou redefinição de senha</p>\
-         <p>Olá! Clique no link abaixo para redefinir a senha:</p>\
-         <p><a href='{}'>Redefinir senha</a></p>",
-        reset_link
-    );
-
-    crate::util::send_html_email(&data.config, &email, "Redefinição de senha", message_body)?;
+    with_pool!(&data.db_pool, |pool| {
+        service::customer::send_password_reset(&mut *pool.acquire().await?, &data, &path).await?
+    });
 
     Ok(HttpResponse::Ok().finish())
 }
 
-#[actix_web::post("/api/customer/reset-password/{token}")]
+#[utoipa::path(
+    post,
+    path = "/api/v1/customer/reset-password/{token}",
+    tag = "customer",
+    params(("token" = String, Path, description = "Token from the reset email")),
+    request_body = String,
+    responses(
+        (status = 200, description = "Password reset", body = String, content_type = "text/plain"),
+        (status = 404, description = "Invalid token", body = String, content_type = "text/plain"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::post("/customer/reset-password/{token}")]
+#[tracing::instrument(skip_all)]
 pub async fn password_reset(
     path: Path<String>,
     data: Data<AppData>,
     body: Json<String>,
 ) -> Result<HttpResponse> {
-    let hashed_password = crate::util::hash(&body);
-
-    let query_result = sqlx::query!(
-        r#"UPDATE customers
-           SET password=?
-           WHERE MD5(email)=?"#,
-        hashed_password,
-        path.into_inner()
-    )
-    .execute(&data.db_pool)
-    .await?;
+    let reset = with_pool!(&data.db_pool, |pool| {
+        service::customer::reset_password(&mut *pool.acquire().await?, &path, &body).await?
+    });
 
-    if query_result.rows_affected() > 0 {
+    if reset {
         Ok(HttpResponse::Ok().body("Senha redefinida com sucesso"))
     } else {
         Ok(HttpResponse::NotFound().body("Token inválido"))
     }
 }
+
+/// Routes of API v1.
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(get_customer)
+        .service(get_customers)
+        .service(login_customer);
+    configure_common(cfg);
+}
+
+/// Routes of API v2, where customers are returned without their password.
+pub fn configure_v2(cfg: &mut ServiceConfig) {
+    cfg.service(get_customer_v2)
+        .service(get_customers_v2)
+        .service(login_customer_v2);
+    configure_common(cfg);
+}
+
+fn configure_common(cfg: &mut ServiceConfig) {
+    cfg.service(create_customer)
+        .service(update_customer)
+        .service(patch_customer)
+        .service(change_customer_password)
+        .service(request_email_change)
+        .service(cancel_email_change)
+        .service(confirm_email_change)
+        .service(cancel_email_change_with_token)
+        .service(delete_customer)
+        .service(activate_customer)
+        .service(send_password_reset)
+        .service(password_reset);
+}
+
+/// Routes mounted under `/admin`.
+pub fn configure_admin(cfg: &mut ServiceConfig) {
+    cfg.service(restore_customer);
+}
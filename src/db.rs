@@ -0,0 +1,223 @@
+use std::{
+    ops::Deref,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    ConnectOptions, Database, MySqlPool, PgPool, Pool, SqlitePool, Transaction,
+    migrate::Migrator,
+    mysql::MySqlConnectOptions,
+    pool::{PoolConnection, PoolOptions},
+    postgres::PgConnectOptions,
+    sqlite::SqliteConnectOptions,
+};
+
+use crate::{Config, Result};
+
+pub static MYSQL_MIGRATOR: Migrator = sqlx::migrate!("./migrations/mysql");
+pub static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DbClient {
+    #[default]
+    Mysql,
+    Postgres,
+    /// For local development and tests, `db_name` is the path of the database file
+    Sqlite,
+}
+
+impl DbClient {
+    /// URL schemes accepted for this backend, the first one being used to build URLs.
+    pub fn schemes(self) -> &'static [&'static str] {
+        match self {
+            Self::Mysql => &["mysql", "mariadb"],
+            Self::Postgres => &["postgres", "postgresql"],
+            Self::Sqlite => &["sqlite"],
+        }
+    }
+
+    pub fn migrator(self) -> &'static Migrator {
+        match self {
+            Self::Mysql => &MYSQL_MIGRATOR,
+            Self::Postgres => &POSTGRES_MIGRATOR,
+            Self::Sqlite => &SQLITE_MIGRATOR,
+        }
+    }
+}
+
+/// Connection pool of the backend selected by `db_client`. Code that runs queries goes through
+/// [`with_pool!`] to get the concrete pool, whose connections implement the repository traits.
+#[derive(Clone, Debug)]
+pub enum DbPool {
+    MySql(MySqlPool),
+    Postgres(PgPool),
+    Sqlite(SqlitePool),
+}
+
+/// Evaluates `$body` with `$pool` bound to the concrete pool of `$db_pool`, as a [`TimedPool`].
+/// The body is expanded once per backend, so it can call generic code and repository methods on
+/// any of them.
+macro_rules! with_pool {
+    ($db_pool:expr, |$pool:ident| $body:expr) => {
+        match $db_pool {
+            $crate::db::DbPool::MySql(inner) => {
+                let $pool = $crate::db::TimedPool(inner);
+                $body
+            }
+            $crate::db::DbPool::Postgres(inner) => {
+                let $pool = $crate::db::TimedPool(inner);
+                $body
+            }
+            $crate::db::DbPool::Sqlite(inner) => {
+                let $pool = $crate::db::TimedPool(inner);
+                $body
+            }
+        }
+    };
+}
+
+pub(crate) use with_pool;
+
+/// Pool of one backend as [`with_pool!`] hands it out. `acquire` and `begin` record how long
+/// they waited for a connection in the `iconery_db_pool_acquire_seconds` histogram, the rest
+/// goes to the pool itself.
+pub struct TimedPool<'p, DB: Database>(pub &'p Pool<DB>);
+
+impl<DB: Database> TimedPool<'_, DB> {
+    pub async fn acquire(&self) -> sqlx::Result<PoolConnection<DB>> {
+        let started_at = Instant::now();
+        let connection = self.0.acquire().await;
+        crate::metrics::observe_db_acquire(started_at.elapsed());
+
+        connection
+    }
+
+    pub async fn begin(&self) -> sqlx::Result<Transaction<'static, DB>> {
+        let started_at = Instant::now();
+        let transaction = self.0.begin().await;
+        crate::metrics::observe_db_acquire(started_at.elapsed());
+
+        transaction
+    }
+}
+
+impl<DB: Database> Deref for TimedPool<'_, DB> {
+    type Target = Pool<DB>;
+
+    fn deref(&self) -> &Pool<DB> {
+        self.0
+    }
+}
+
+impl DbPool {
+    /// Every statement is logged with its timing on `sqlx::query` at debug level, and the slow
+    /// ones at warn level.
+    pub async fn connect(config: &Config) -> Result<Self> {
+        let database_url = config.database_url();
+
+        let db_pool = match config.db_client {
+            DbClient::Mysql => Self::MySql(
+                pool_options(config)
+                    .connect_with(log_statements(
+                        database_url.parse::<MySqlConnectOptions>()?,
+                        config,
+                    ))
+                    .await?,
+            ),
+            DbClient::Postgres => Self::Postgres(
+                pool_options(config)
+                    .connect_with(log_statements(
+                        database_url.parse::<PgConnectOptions>()?,
+                        config,
+                    ))
+                    .await?,
+            ),
+            DbClient::Sqlite => Self::Sqlite(
+                pool_options(config)
+                    .connect_with(log_statements(
+                        database_url
+                            .parse::<SqliteConnectOptions>()?
+                            .create_if_missing(true),
+                        config,
+                    ))
+                    .await?,
+            ),
+        };
+
+        Ok(db_pool)
+    }
+
+    pub fn client(&self) -> DbClient {
+        match self {
+            Self::MySql(_) => DbClient::Mysql,
+            Self::Postgres(_) => DbClient::Postgres,
+            Self::Sqlite(_) => DbClient::Sqlite,
+        }
+    }
+
+    pub async fn migrate(&self) -> Result<()> {
+        let migrator = self.client().migrator();
+        with_pool!(self, |pool| migrator.run(&*pool).await)
+            .map_err(|error| sqlx::Error::Migrate(Box::new(error)))?;
+
+        Ok(())
+    }
+
+    pub async fn ping(&self) -> Result<()> {
+        with_pool!(self, |pool| sqlx::query("SELECT 1")
+            .execute(&*pool)
+            .await
+            .map(drop))?;
+
+        Ok(())
+    }
+
+    pub async fn applied_migrations(&self) -> Result<Vec<i64>> {
+        const QUERY: &str = "SELECT version FROM _sqlx_migrations WHERE success=TRUE";
+
+        let versions = with_pool!(self, |pool| sqlx::query_scalar(QUERY)
+            .fetch_all(&*pool)
+            .await)?;
+
+        Ok(versions)
+    }
+
+    pub fn size(&self) -> u32 {
+        with_pool!(self, |pool| pool.size())
+    }
+
+    pub fn num_idle(&self) -> usize {
+        with_pool!(self, |pool| pool.num_idle())
+    }
+
+    pub fn max_connections(&self) -> u32 {
+        with_pool!(self, |pool| pool.options().get_max_connections())
+    }
+
+    pub async fn close(&self) {
+        with_pool!(self, |pool| pool.close().await)
+    }
+}
+
+fn pool_options<DB: Database>(config: &Config) -> PoolOptions<DB> {
+    let idle_timeout =
+        (config.db_idle_timeout_secs > 0).then(|| Duration::from_secs(config.db_idle_timeout_secs));
+
+    PoolOptions::new()
+        .min_connections(config.db_min_connections)
+        .max_connections(config.db_max_connections)
+        .acquire_timeout(Duration::from_secs(config.db_acquire_timeout_secs))
+        .idle_timeout(idle_timeout)
+}
+
+fn log_statements<O: ConnectOptions>(options: O, config: &Config) -> O {
+    options
+        .log_statements(log::LevelFilter::Debug)
+        .log_slow_statements(
+            log::LevelFilter::Warn,
+            Duration::from_millis(config.db_slow_statement_ms),
+        )
+}
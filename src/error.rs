@@ -1,4 +1,8 @@
-use actix_web::{HttpResponse, ResponseError, body::BoxBody, http::StatusCode};
+use actix_web::{
+    HttpResponse, ResponseError,
+    body::BoxBody,
+    http::{StatusCode, header},
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -10,20 +14,50 @@ pub enum Error {
     EmailContentError(#[from] lettre::error::Error),
     #[error("a smtp error occurred: {0}")]
     SmtpError(#[from] lettre::transport::smtp::Error),
+    #[error("an error writing an email file occurred: {0}")]
+    MailFileError(#[from] lettre::transport::file::Error),
+    #[error("an error in email template occurred: {0}")]
+    TemplateError(#[from] minijinja::Error),
+    #[error("a conflict occurred: {0}")]
+    ConflictError(String),
+    #[error("muitas requisições, tente novamente em {retry_after_secs} segundos")]
+    RateLimitError { retry_after_secs: u64 },
+    #[error("credenciais de administrador ausentes ou inválidas")]
+    UnauthorizedError,
 }
 
 impl ResponseError for Error {
     fn status_code(&self) -> StatusCode {
         match &self {
             Self::DatabaseError(sqlx::Error::RowNotFound) => StatusCode::NOT_FOUND,
+            Self::DatabaseError(sqlx::Error::Database(error)) if error.is_unique_violation() => {
+                StatusCode::CONFLICT
+            }
+            Self::ConflictError(_) => StatusCode::CONFLICT,
+            Self::RateLimitError { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::UnauthorizedError => StatusCode::UNAUTHORIZED,
             Self::DatabaseError(_)
             | Self::EmailAddressError(_)
             | Self::EmailContentError(_)
-            | Self::SmtpError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            | Self::SmtpError(_)
+            | Self::MailFileError(_)
+            | Self::TemplateError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        HttpResponse::build(self.status_code()).body(self.to_string())
+        let mut response = HttpResponse::build(self.status_code());
+
+        match self {
+            Self::RateLimitError { retry_after_secs } => {
+                response.insert_header((header::RETRY_AFTER, *retry_after_secs));
+            }
+            Self::UnauthorizedError => {
+                response.insert_header((header::WWW_AUTHENTICATE, r#"Basic realm="admin""#));
+            }
+            _ => {}
+        }
+
+        response.body(self.to_string())
     }
 }
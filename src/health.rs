@@ -0,0 +1,182 @@
+use std::{
+    collections::HashSet,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, ServiceConfig},
+};
+use chrono::DateTime;
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::watch;
+
+use crate::AppData;
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+const MAIL_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Past this, the last mail check is reported stale, as when the next one hangs.
+const MAIL_CHECK_TTL: Duration = Duration::from_secs(90);
+
+/// Last result of the mail transport check, kept by [`run_mail_probe`]. `/ready` only reads it,
+/// so a slow SMTP server neither holds readiness requests nor piles up blocking checks.
+#[derive(Default)]
+pub struct MailStatus {
+    last_check: Mutex<Option<(Instant, Result<(), String>)>>,
+}
+
+#[derive(Serialize)]
+pub struct DependencyStatus {
+    pub status: &'static str,
+    pub critical: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl DependencyStatus {
+    fn up(critical: bool, detail: Option<String>) -> Self {
+        Self {
+            status: "up",
+            critical,
+            detail,
+        }
+    }
+
+    fn down(critical: bool, detail: String) -> Self {
+        Self {
+            status: "down",
+            critical,
+            detail: Some(detail),
+        }
+    }
+
+    fn is_failing(&self) -> bool {
+        self.critical && self.status != "up"
+    }
+}
+
+#[actix_web::get("/health")]
+pub async fn health() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "status": "ok" }))
+}
+
+#[actix_web::get("/ready")]
+pub async fn ready(data: Data<AppData>) -> HttpResponse {
+    let database = check_database(&data).await;
+    let migrations = check_migrations(&data).await;
+    let mail = check_mail(&data);
+
+    let is_ready = ![&database, &migrations, &mail]
+        .iter()
+        .any(|dependency| dependency.is_failing());
+
+    let body = json!({
+        "status": if is_ready { "ready" } else { "not_ready" },
+        "checks": {
+            "database": database,
+            "migrations": migrations,
+            "mail": mail,
+        },
+    });
+
+    if is_ready {
+        HttpResponse::Ok().json(body)
+    } else {
+        HttpResponse::ServiceUnavailable().json(body)
+    }
+}
+
+#[actix_web::get("/version")]
+pub async fn version() -> HttpResponse {
+    let build_time = env!("ICONERY_BUILD_TIMESTAMP")
+        .parse::<i64>()
+        .ok()
+        .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+        .map(|build_time| build_time.to_rfc3339());
+
+    HttpResponse::Ok().json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_hash": env!("ICONERY_GIT_HASH"),
+        "build_time": build_time,
+    }))
+}
+
+async fn check_database(data: &AppData) -> DependencyStatus {
+    let query = data.db_pool.ping();
+
+    match actix_web::rt::time::timeout(CHECK_TIMEOUT, query).await {
+        Ok(Ok(_)) => DependencyStatus::up(true, None),
+        Ok(Err(error)) => DependencyStatus::down(true, error.to_string()),
+        Err(_) => DependencyStatus::down(true, "timed out".to_owned()),
+    }
+}
+
+/// Not critical, emails wait in the outbox until the mail transport is back.
+fn check_mail(data: &AppData) -> DependencyStatus {
+    match &*data.mail_status.last_check.lock().unwrap() {
+        None => DependencyStatus::down(false, "not checked yet".to_owned()),
+        Some((checked_at, _)) if checked_at.elapsed() > MAIL_CHECK_TTL => DependencyStatus::down(
+            false,
+            format!("last checked {}s ago", checked_at.elapsed().as_secs()),
+        ),
+        Some((_, Ok(()))) => DependencyStatus::up(false, None),
+        Some((_, Err(detail))) => DependencyStatus::down(false, detail.clone()),
+    }
+}
+
+/// Checks the mail transport every [`MAIL_CHECK_INTERVAL`], one check at a time, until shutdown.
+pub async fn run_mail_probe(data: AppData, mut shutdown: watch::Receiver<bool>) {
+    while !*shutdown.borrow() {
+        tokio::select! {
+            _ = probe_mail(&data) => {}
+            _ = shutdown.changed() => break,
+        }
+
+        tokio::select! {
+            _ = actix_web::rt::time::sleep(MAIL_CHECK_INTERVAL) => {}
+            _ = shutdown.changed() => {}
+        }
+    }
+
+    tracing::info!("mail probe stopped");
+}
+
+/// Checks the mail transport once, storing the result for `/ready`.
+pub async fn probe_mail(data: &AppData) {
+    let mailer = data.mailer.clone();
+    let result = actix_web::rt::task::spawn_blocking(move || mailer.check())
+        .await
+        .unwrap_or_else(|error| Err(error.to_string()));
+
+    *data.mail_status.last_check.lock().unwrap() = Some((Instant::now(), result));
+}
+
+async fn check_migrations(data: &AppData) -> DependencyStatus {
+    let query = data.db_pool.applied_migrations();
+
+    let applied = match actix_web::rt::time::timeout(CHECK_TIMEOUT, query).await {
+        Ok(Ok(versions)) => versions.into_iter().collect::<HashSet<_>>(),
+        Ok(Err(error)) => return DependencyStatus::down(true, error.to_string()),
+        Err(_) => return DependencyStatus::down(true, "timed out".to_owned()),
+    };
+
+    let pending = data
+        .db_pool
+        .client()
+        .migrator()
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .count();
+
+    if pending == 0 {
+        DependencyStatus::up(true, None)
+    } else {
+        DependencyStatus::down(true, format!("{pending} pending migration(s)"))
+    }
+}
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(health).service(ready).service(version);
+}
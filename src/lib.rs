@@ -0,0 +1,102 @@
+pub mod admin;
+pub mod cli;
+pub mod config;
+pub mod cors;
+pub mod customer;
+pub mod db;
+pub mod error;
+pub mod health;
+pub mod links;
+pub mod logging;
+pub mod mailer;
+pub mod metrics;
+pub mod openapi;
+pub mod order;
+pub mod outbox;
+pub mod product;
+pub mod rate_limit;
+pub mod repository;
+pub mod retention;
+pub mod seed;
+pub mod server;
+pub mod service;
+pub mod templates;
+pub mod tls;
+pub mod util;
+pub mod versioning;
+
+use std::sync::Arc;
+
+use actix_web::{
+    App,
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    middleware::from_fn,
+    web::{self, Data, JsonConfig, PayloadConfig, ServiceConfig},
+};
+
+pub use config::Config;
+
+pub type Result<T> = std::result::Result<T, crate::error::Error>;
+
+#[derive(Clone)]
+pub struct AppData {
+    pub config: Arc<Config>,
+    pub db_pool: db::DbPool,
+    pub mailer: Arc<dyn mailer::Mailer>,
+    pub mail_status: Arc<health::MailStatus>,
+    pub metrics: Arc<metrics::Metrics>,
+    pub rate_limiter: Arc<rate_limit::RateLimiter>,
+    pub templates: Arc<templates::Templates>,
+}
+
+/// The whole API with its middleware, ready for `HttpServer::new` or `actix_web::test`.
+pub fn build_app(
+    app_data: AppData,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let max_body_bytes = app_data.config.server_max_body_bytes;
+
+    App::new()
+        .app_data(Data::new(app_data))
+        .app_data(JsonConfig::default().limit(max_body_bytes))
+        .app_data(PayloadConfig::new(max_body_bytes))
+        .wrap(from_fn(rate_limit::limit_requests))
+        .wrap(from_fn(cors::handle_cors))
+        .wrap(from_fn(metrics::track_requests))
+        .wrap(from_fn(logging::trace_request))
+        .configure(health::configure)
+        .configure(metrics::configure)
+        .configure(openapi::configure)
+        .service(web::scope("/api/v1").configure(api_v1))
+        .service(web::scope("/api/v2").configure(api_v2))
+        // Legacy unversioned paths, registered last so the versioned scopes match first
+        .service(
+            web::scope("/api")
+                .wrap(from_fn(versioning::deprecate_legacy))
+                .configure(api_v1),
+        )
+}
+
+/// Routes under `/api/v1`, also served by the legacy `/api` paths.
+pub fn api_v1(cfg: &mut ServiceConfig) {
+    product::configure(cfg);
+    customer::configure(cfg);
+    order::configure(cfg);
+    admin::configure(cfg);
+}
+
+/// Routes under `/api/v2`.
+pub fn api_v2(cfg: &mut ServiceConfig) {
+    product::configure(cfg);
+    customer::configure_v2(cfg);
+    order::configure(cfg);
+    admin::configure(cfg);
+}
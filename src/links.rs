@@ -0,0 +1,53 @@
+use crate::Config;
+
+/// Base URL the API is reached at by email recipients. Falls back to the bind address, which is
+/// only right when the server is exposed directly.
+pub fn public_base_url(config: &Config) -> String {
+    match &config.public_base_url {
+        Some(url) => url.trim_end_matches('/').to_owned(),
+        None => {
+            let scheme = if config.tls_enabled() {
+                "https"
+            } else {
+                "http"
+            };
+            format!("{scheme}://{}:{}", config.server_host, config.server_port)
+        }
+    }
+}
+
+/// Base URL of the storefront, where the user-facing forms live.
+pub fn frontend_base_url(config: &Config) -> String {
+    match &config.frontend_base_url {
+        Some(url) => url.trim_end_matches('/').to_owned(),
+        None => public_base_url(config),
+    }
+}
+
+pub fn activation_link(config: &Config, token: &str) -> String {
+    format!(
+        "{}/api/v1/customer/activate/{token}",
+        public_base_url(config)
+    )
+}
+
+pub fn password_reset_link(config: &Config, token: &str) -> String {
+    format!(
+        "{}/reset-password-form?token={token}",
+        frontend_base_url(config)
+    )
+}
+
+pub fn email_change_confirm_link(config: &Config, token: &str) -> String {
+    format!(
+        "{}/api/v1/customer/confirm-email/{token}",
+        public_base_url(config)
+    )
+}
+
+pub fn email_change_cancel_link(config: &Config, token: &str) -> String {
+    format!(
+        "{}/api/v1/customer/cancel-email/{token}",
+        public_base_url(config)
+    )
+}
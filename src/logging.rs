@@ -0,0 +1,140 @@
+use std::time::Instant;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+};
+use serde::{Deserialize, Serialize};
+use tracing::Instrument;
+use tracing_subscriber::EnvFilter;
+
+use crate::Config;
+
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Path segments whose next segment carries a secret (a token or an email address).
+const SENSITIVE_SEGMENTS: &[&str] = &[
+    "activate",
+    "reset-password",
+    "confirm-email",
+    "cancel-email",
+];
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Json,
+    Text,
+}
+
+/// `log_level` takes `EnvFilter` directives, e.g. `info,sqlx::query=debug` to see the timing of
+/// every statement.
+pub fn init(config: &Config) -> anyhow::Result<()> {
+    let filter = EnvFilter::try_new(&config.log_level)?;
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+
+    match config.log_format {
+        LogFormat::Json => subscriber
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .try_init(),
+        LogFormat::Text => subscriber.try_init(),
+    }
+    .map_err(|error| anyhow::anyhow!(error))
+}
+
+/// Wraps each request in a span with its id, method and path, and logs its outcome.
+/// The id is taken from `X-Request-Id` when the client sends one, and is echoed back.
+pub async fn trace_request(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 64)
+        .map(str::to_owned)
+        .unwrap_or_else(crate::util::generate_token);
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %redact_path(request.path()),
+        route = request.match_pattern().as_deref().unwrap_or("unmatched"),
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+    );
+
+    let started_at = Instant::now();
+    let mut response = next.call(request).instrument(span.clone()).await?;
+    let status = response.status();
+
+    span.record("status", status.as_u16());
+    span.record("latency_ms", started_at.elapsed().as_millis() as u64);
+
+    let _entered = span.enter();
+    match response.response().error() {
+        Some(error) if status.is_server_error() => {
+            tracing::error!(error = %redact_emails(&error.to_string()), "request failed")
+        }
+        Some(error) => {
+            tracing::warn!(error = %redact_emails(&error.to_string()), "request rejected")
+        }
+        None => tracing::info!("request completed"),
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    Ok(response)
+}
+
+pub fn redact_path(path: &str) -> String {
+    let mut redacted = Vec::new();
+    let mut hide_next = false;
+
+    for segment in path.split('/') {
+        if hide_next && !segment.is_empty() {
+            redacted.push("[redacted]");
+        } else {
+            redacted.push(segment);
+        }
+        hide_next = SENSITIVE_SEGMENTS.contains(&segment);
+    }
+
+    redacted.join("/")
+}
+
+/// Keeps the first character and the domain, e.g. `j***@example.com`.
+pub fn redact_email(email: &str) -> String {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            let first = local.chars().next().map(String::from).unwrap_or_default();
+            format!("{first}***@{domain}")
+        }
+        None => "[redacted]".to_owned(),
+    }
+}
+
+/// Redacts anything that looks like an email address in free text, such as database errors
+/// quoting a duplicate entry.
+pub fn redact_emails(text: &str) -> String {
+    text.split_inclusive(|c: char| c.is_whitespace() || c == '\'' || c == '"')
+        .map(|word| {
+            let trimmed =
+                word.trim_end_matches(|c: char| c.is_whitespace() || c == '\'' || c == '"');
+            if trimmed.contains('@') {
+                format!("{}{}", redact_email(trimmed), &word[trimmed.len()..])
+            } else {
+                word.to_owned()
+            }
+        })
+        .collect()
+}
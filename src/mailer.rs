@@ -0,0 +1,177 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use lettre::{
+    FileTransport, Message, SmtpTransport, Transport, message::MultiPart,
+    transport::smtp::authentication::Credentials,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{Config, Result};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MailTransport {
+    #[default]
+    Smtp,
+    File,
+    /// Only logs that an email was sent, never its body, which may hold tokens
+    Log,
+    Memory,
+}
+
+#[derive(Clone, Debug)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub html_body: String,
+    pub text_body: String,
+}
+
+/// Delivers emails synchronously. Implementations may block, so callers on an async worker
+/// should go through `spawn_blocking`.
+pub trait Mailer: Send + Sync {
+    fn send(&self, email: &Email) -> Result<()>;
+
+    /// Whether emails can be delivered right now, or why not. Blocks like `send`.
+    fn check(&self) -> std::result::Result<(), String> {
+        Ok(())
+    }
+}
+
+pub fn from_config(config: &Config) -> Result<Arc<dyn Mailer>> {
+    let mailer: Arc<dyn Mailer> = match config.mail_transport {
+        MailTransport::Smtp => Arc::new(SmtpMailer::new(config)?),
+        MailTransport::File => Arc::new(FileMailer::new(config)),
+        MailTransport::Log => Arc::new(LogMailer),
+        MailTransport::Memory => Arc::new(MemoryMailer::default()),
+    };
+
+    Ok(mailer)
+}
+
+fn build_message(from: &str, email: &Email) -> Result<Message> {
+    let message = Message::builder()
+        .from(from.parse()?)
+        .to(email.to.parse()?)
+        .subject(&email.subject)
+        .multipart(MultiPart::alternative_plain_html(
+            email.text_body.clone(),
+            email.html_body.clone(),
+        ))?;
+
+    Ok(message)
+}
+
+/// Keeps a single pooled transport, so connections are reused between sends.
+pub struct SmtpMailer {
+    from: String,
+    transport: SmtpTransport,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &Config) -> Result<Self> {
+        let transport = SmtpTransport::starttls_relay(&config.smtp_host)?
+            .credentials(Credentials::new(
+                config.smtp_user.clone(),
+                config.smtp_password.clone(),
+            ))
+            .port(config.smtp_port)
+            .build();
+
+        Ok(Self {
+            from: config.smtp_from.clone(),
+            transport,
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, email: &Email) -> Result<()> {
+        self.transport.send(&build_message(&self.from, email)?)?;
+
+        Ok(())
+    }
+
+    fn check(&self) -> std::result::Result<(), String> {
+        match self.transport.test_connection() {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("the SMTP server did not answer".to_owned()),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+}
+
+/// Writes every email as an `.eml` file into `mail_dir`.
+pub struct FileMailer {
+    from: String,
+    dir: PathBuf,
+    transport: FileTransport,
+}
+
+impl FileMailer {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            from: config.smtp_from.clone(),
+            dir: PathBuf::from(&config.mail_dir),
+            transport: FileTransport::new(&config.mail_dir),
+        }
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, email: &Email) -> Result<()> {
+        self.transport.send(&build_message(&self.from, email)?)?;
+
+        Ok(())
+    }
+
+    /// Writes and removes a file, since permissions alone don't tell whether writes succeed.
+    fn check(&self) -> std::result::Result<(), String> {
+        let probe_path = self.dir.join(".ready");
+
+        std::fs::write(&probe_path, b"")
+            .and_then(|()| std::fs::remove_file(&probe_path))
+            .map_err(|error| format!("{}: {error}", self.dir.display()))
+    }
+}
+
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send(&self, email: &Email) -> Result<()> {
+        tracing::info!(
+            to = %crate::logging::redact_email(&email.to),
+            subject = %email.subject,
+            "email not sent, logged only"
+        );
+
+        Ok(())
+    }
+}
+
+/// Keeps sent emails in memory so tests can assert on them.
+#[derive(Default)]
+pub struct MemoryMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+impl MemoryMailer {
+    pub fn sent(&self) -> Vec<Email> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.sent.lock().unwrap().clear();
+    }
+}
+
+impl Mailer for MemoryMailer {
+    fn send(&self, email: &Email) -> Result<()> {
+        self.sent.lock().unwrap().push(email.clone());
+
+        Ok(())
+    }
+}
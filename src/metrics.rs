@@ -0,0 +1,317 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    HttpResponse,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web::{Data, ServiceConfig},
+};
+
+use crate::{AppData, db::DbPool};
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Time waited for a DB connection by every `acquire` and `begin` through `with_pool!`. Kept for
+/// the whole process, like the pools themselves, since connections are taken where no `Metrics`
+/// is at hand.
+static DB_ACQUIRE: LazyLock<Mutex<Histogram>> = LazyLock::new(|| Mutex::new(Histogram::new()));
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RequestLabels {
+    method: String,
+    route: String,
+    status: u16,
+}
+
+#[derive(Clone)]
+struct Histogram {
+    bucket_counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            bucket_counts: vec![0; LATENCY_BUCKETS.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, seconds: f64) {
+        for (bucket_count, upper_bound) in self.bucket_counts.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= *upper_bound {
+                *bucket_count += 1;
+            }
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    fn write(&self, output: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+
+        for (bucket_count, upper_bound) in self.bucket_counts.iter().zip(LATENCY_BUCKETS) {
+            let _ = writeln!(
+                output,
+                "{name}_bucket{{{labels}{separator}le=\"{upper_bound}\"}} {bucket_count}"
+            );
+        }
+        let _ = writeln!(
+            output,
+            "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {}",
+            self.count
+        );
+        let braced_labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{labels}}}")
+        };
+        let _ = writeln!(output, "{name}_sum{braced_labels} {}", self.sum);
+        let _ = writeln!(output, "{name}_count{braced_labels} {}", self.count);
+    }
+}
+
+/// Process-wide counters, exposed in the Prometheus text format on `/metrics`.
+pub struct Metrics {
+    http_requests: Mutex<BTreeMap<RequestLabels, Histogram>>,
+    emails_sent: AtomicU64,
+    emails_failed: AtomicU64,
+    orders_created: AtomicU64,
+    order_value: AtomicU64,
+    signups: AtomicU64,
+    activations: AtomicU64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            http_requests: Mutex::new(BTreeMap::new()),
+            emails_sent: AtomicU64::new(0),
+            emails_failed: AtomicU64::new(0),
+            orders_created: AtomicU64::new(0),
+            order_value: AtomicU64::new(0),
+            signups: AtomicU64::new(0),
+            activations: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, latency: Duration) {
+        let labels = RequestLabels {
+            method: method.to_owned(),
+            route: route.to_owned(),
+            status,
+        };
+
+        self.http_requests
+            .lock()
+            .unwrap()
+            .entry(labels)
+            .or_insert_with(Histogram::new)
+            .observe(latency.as_secs_f64());
+    }
+
+    pub fn email_sent(&self) {
+        self.emails_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn email_failed(&self) {
+        self.emails_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn order_created(&self, total_price: i64) {
+        self.orders_created.fetch_add(1, Ordering::Relaxed);
+        self.order_value
+            .fetch_add(total_price.max(0) as u64, Ordering::Relaxed);
+    }
+
+    pub fn customer_signed_up(&self) {
+        self.signups.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn customer_activated(&self) {
+        self.activations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn render(&self, db_pool: &DbPool) -> String {
+        let mut output = String::new();
+
+        let http_requests = self.http_requests.lock().unwrap().clone();
+
+        write_header(
+            &mut output,
+            "iconery_http_requests_total",
+            "counter",
+            "HTTP requests by method, route and status",
+        );
+        for (labels, histogram) in &http_requests {
+            let _ = writeln!(
+                output,
+                "iconery_http_requests_total{{{}}} {}",
+                request_labels(labels),
+                histogram.count
+            );
+        }
+
+        write_header(
+            &mut output,
+            "iconery_http_request_duration_seconds",
+            "histogram",
+            "HTTP request latency by method, route and status",
+        );
+        for (labels, histogram) in &http_requests {
+            histogram.write(
+                &mut output,
+                "iconery_http_request_duration_seconds",
+                &request_labels(labels),
+            );
+        }
+
+        for (name, help, value) in [
+            (
+                "iconery_db_pool_connections",
+                "Open DB connections",
+                db_pool.size() as u64,
+            ),
+            (
+                "iconery_db_pool_idle_connections",
+                "Idle DB connections",
+                db_pool.num_idle() as u64,
+            ),
+            (
+                "iconery_db_pool_max_connections",
+                "Maximum DB connections",
+                db_pool.max_connections() as u64,
+            ),
+        ] {
+            write_header(&mut output, name, "gauge", help);
+            let _ = writeln!(output, "{name} {value}");
+        }
+
+        write_header(
+            &mut output,
+            "iconery_db_pool_acquire_seconds",
+            "histogram",
+            "Time waited for a DB connection",
+        );
+        DB_ACQUIRE
+            .lock()
+            .unwrap()
+            .write(&mut output, "iconery_db_pool_acquire_seconds", "");
+
+        for (name, help, counter) in [
+            (
+                "iconery_emails_sent_total",
+                "Emails delivered by the outbox worker",
+                &self.emails_sent,
+            ),
+            (
+                "iconery_emails_failed_total",
+                "Failed email delivery attempts",
+                &self.emails_failed,
+            ),
+            (
+                "iconery_orders_created_total",
+                "Orders created",
+                &self.orders_created,
+            ),
+            (
+                "iconery_order_value_total",
+                "Sum of the total price of the orders created",
+                &self.order_value,
+            ),
+            (
+                "iconery_customer_signups_total",
+                "Customers signed up",
+                &self.signups,
+            ),
+            (
+                "iconery_customer_activations_total",
+                "Customers activated",
+                &self.activations,
+            ),
+        ] {
+            write_header(&mut output, name, "counter", help);
+            let _ = writeln!(output, "{name} {}", counter.load(Ordering::Relaxed));
+        }
+
+        output
+    }
+}
+
+pub fn observe_db_acquire(wait: Duration) {
+    DB_ACQUIRE.lock().unwrap().observe(wait.as_secs_f64());
+}
+
+fn write_header(output: &mut String, name: &str, metric_type: &str, help: &str) {
+    let _ = writeln!(output, "# HELP {name} {help}");
+    let _ = writeln!(output, "# TYPE {name} {metric_type}");
+}
+
+fn request_labels(labels: &RequestLabels) -> String {
+    format!(
+        "method=\"{}\",route=\"{}\",status=\"{}\"",
+        escape_label(&labels.method),
+        escape_label(&labels.route),
+        labels.status
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Counts every request under its route pattern rather than its path, so ids don't blow up the
+/// number of series.
+pub async fn track_requests(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let method = request.method().to_string();
+    let route = request
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_owned());
+    let maybe_data = request.app_data::<Data<AppData>>().cloned();
+
+    let started_at = Instant::now();
+    let response = next.call(request).await?;
+
+    if let Some(data) = maybe_data {
+        data.metrics.observe_request(
+            &method,
+            &route,
+            response.status().as_u16(),
+            started_at.elapsed(),
+        );
+    }
+
+    Ok(response)
+}
+
+#[actix_web::get("/metrics")]
+pub async fn metrics(data: Data<AppData>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(data.metrics.render(&data.db_pool))
+}
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(metrics);
+}
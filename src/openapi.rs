@@ -0,0 +1,124 @@
+use actix_web::{HttpResponse, web::ServiceConfig};
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+
+use crate::{customer, order, outbox, product, retention};
+
+/// Swagger UI loading the spec below. The assets come from the CDN so nothing is downloaded at
+/// build time.
+const SWAGGER_UI_PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Iconery API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5.29.4/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5.29.4/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Iconery API",
+        description = "Every `/api/v1` route is also under `/api/v2`, except for the customer \
+                       routes redefined there. The unversioned `/api` paths are deprecated \
+                       aliases of `/api/v1`. The `/admin` routes require the HTTP Basic \
+                       credentials of an admin account, made with the `create-admin` command; \
+                       the others need no authentication. Errors come back as plain text."
+    ),
+    modifiers(&AdminAuth),
+    paths(
+        product::create_product,
+        product::update_product,
+        product::patch_product,
+        product::delete_product,
+        product::restore_product,
+        product::get_product,
+        product::get_products,
+        product::get_featured_products,
+        product::get_products_with_search,
+        product::get_any_product,
+        product::get_products_by_status,
+        customer::create_customer,
+        customer::update_customer,
+        customer::patch_customer,
+        customer::change_customer_password,
+        customer::request_email_change,
+        customer::cancel_email_change,
+        customer::confirm_email_change,
+        customer::cancel_email_change_with_token,
+        customer::delete_customer,
+        customer::restore_customer,
+        customer::get_customer,
+        customer::get_customers,
+        customer::login_customer,
+        customer::activate_customer,
+        customer::send_password_reset,
+        customer::password_reset,
+        customer::login_customer_v2,
+        customer::get_customer_v2,
+        customer::get_customers_v2,
+        order::create_order,
+        order::update_order,
+        order::patch_order,
+        order::delete_order,
+        order::get_order,
+        order::get_orders,
+        order::get_orders_by_customer,
+        outbox::get_outbox_emails,
+        outbox::retry_outbox_email,
+        retention::purge_deleted,
+    ),
+    tags(
+        (name = "product", description = "Catalog"),
+        (name = "customer", description = "Accounts, activation and credentials"),
+        (name = "order", description = "Orders and their items"),
+        (name = "admin", description = "Operations, to be kept internal"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the `admin_auth` scheme the admin operations refer to.
+struct AdminAuth;
+
+impl Modify for AdminAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "admin_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Basic)
+                    .description(Some("Email and password of an admin account"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[actix_web::get("/api/openapi.json")]
+pub async fn openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+#[actix_web::get("/api/docs")]
+pub async fn swagger_ui() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(SWAGGER_UI_PAGE)
+}
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(openapi_json).service(swagger_ui);
+}
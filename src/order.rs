@@ -1,270 +1,226 @@
 use actix_web::{
     HttpResponse,
-    web::{Data, Json, Path},
+    web::{Data, Json, Path, ServiceConfig},
 };
 use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
 
-use crate::{AppData, Result};
+use crate::{AppData, Result, db::with_pool, repository::OrderRepo, service};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct OrderRequest {
     pub customer_id: i64,
     pub items: Vec<OrderItemRequest>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
+pub struct OrderPatchRequest {
+    pub customer_id: Option<i64>,
+    pub items: Option<Vec<OrderItemRequest>>,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct OrderItemRequest {
     pub product_id: i64,
     pub amount: i64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct OrderResponse {
     pub id: i64,
     pub customer_id: i64,
     pub items: Vec<OrderItemResponse>,
 }
 
-#[derive(Serialize)]
+/// Product details come along even once the product is deleted, which hides it everywhere else.
+#[derive(FromRow, Serialize, ToSchema)]
 pub struct OrderItemResponse {
     pub id: i64,
     pub product_id: i64,
+    pub product_name: String,
+    pub product_price: i64,
     pub amount: i64,
 }
 
-#[actix_web::post("/api/order")]
-pub async fn create_order(data: Data<AppData>, body: Json<OrderRequest>) -> Result<HttpResponse> {
-    let query_result = sqlx::query!(
-        r#"INSERT INTO orders (customer_id)
-           VALUES (?)"#,
-        body.customer_id
+#[utoipa::path(
+    post,
+    path = "/api/v1/order",
+    tag = "order",
+    request_body = OrderRequest,
+    responses(
+        (status = 200, description = "Order created and confirmation email queued"),
+        (status = 404, description = "Unknown customer or product"),
+        (status = 409, description = "A product is not published", body = String, content_type = "text/plain"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .execute(&data.db_pool)
-    .await?;
-
-    for item in &body.items {
-        sqlx::query!(
-            r#"INSERT INTO order_items (order_id, product_id, amount)
-               VALUES (?, ?, ?)"#,
-            query_result.last_insert_id(),
-            item.product_id,
-            item.amount,
-        )
-        .execute(&data.db_pool)
-        .await?;
-    }
-
-    let mut total_order_price = 0i64;
-    let mut items_html = String::new();
-    for item in body.items.iter() {
-        let product_record = sqlx::query!(
-            r#"SELECT name, price FROM products WHERE id=?"#,
-            item.product_id
-        )
-        .fetch_one(&data.db_pool)
-        .await?;
-
-        let total_price = item.amount * product_record.price;
-        total_order_price += total_price;
-
-        items_html.push_str(&format!(
-            "<li>Nome do produto: {} - quantidade: {} - preço total: R${}",
-            product_record.name, item.amount, total_price
-        ));
-    }
-
-    let customer_record = sqlx::query!(
-        r#"SELECT name, email FROM customers WHERE id=?"#,
-        body.customer_id
-    )
-    .fetch_one(&data.db_pool)
-    .await?;
-
-    let message_body = format!(
-        "<h3>Confirmação de pedido</h3>\
-         <p>Olá, {}! Seu pedido (ID: {}) foi recebido.<br>\
-         Total: {}<br></p>\
-         <ul>{}</ul>\
-         <p>Obrigado por comprar conosco!</p>",
-        customer_record.name,
-        query_result.last_insert_id(),
-        total_order_price,
-        items_html
-    );
+)]
+#[actix_web::post("/order")]
+#[tracing::instrument(skip_all, fields(order_id = tracing::field::Empty, customer_id = body.customer_id))]
+pub async fn create_order(data: Data<AppData>, body: Json<OrderRequest>) -> Result<HttpResponse> {
+    let order = with_pool!(&data.db_pool, |pool| {
+        let mut transaction = pool.begin().await?;
+        let order = service::order::create_order(&mut *transaction, &data, &body).await?;
+        transaction.commit().await?;
+        order
+    });
 
-    crate::util::send_html_email(
-        &data.config,
-        &customer_record.email,
-        "Confirmação de pedido",
-        message_body,
-    )?;
+    tracing::Span::current().record("order_id", order.id);
+    data.metrics.order_created(order.total_price);
 
     Ok(HttpResponse::Ok().finish())
 }
 
-#[actix_web::put("/api/order/{id:\\d+}")]
+#[utoipa::path(
+    put,
+    path = "/api/v1/order/{id}",
+    tag = "order",
+    params(("id" = i64, Path, description = "Order id")),
+    request_body = OrderRequest,
+    responses(
+        (status = 200, description = "Order replaced"),
+        (status = 404, description = "Unknown customer or product"),
+        (status = 409, description = "A product is not published", body = String, content_type = "text/plain"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::put("/order/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(order_id = %path, customer_id = body.customer_id))]
 pub async fn update_order(
     path: Path<i64>,
     data: Data<AppData>,
     body: Json<OrderRequest>,
 ) -> Result<HttpResponse> {
-    let id = path.into_inner();
-
-    sqlx::query!(
-        r#"UPDATE orders
-           SET customer_id=?
-           WHERE id=?"#,
-        body.customer_id,
-        id
-    )
-    .execute(&data.db_pool)
-    .await?;
+    with_pool!(&data.db_pool, |pool| {
+        let mut transaction = pool.begin().await?;
+        service::order::update_order(&mut *transaction, path.into_inner(), &body).await?;
+        transaction.commit().await?;
+    });
 
-    sqlx::query!("DELETE FROM order_items WHERE order_id=?", id)
-        .execute(&data.db_pool)
-        .await?;
+    Ok(HttpResponse::Ok().finish())
+}
 
-    for item in &body.items {
-        sqlx::query!(
-            r#"INSERT INTO order_items (order_id, product_id, amount)
-               VALUES (?, ?, ?)"#,
-            id,
-            item.product_id,
-            item.amount
-        )
-        .execute(&data.db_pool)
-        .await?;
-    }
+#[utoipa::path(
+    patch,
+    path = "/api/v1/order/{id}",
+    tag = "order",
+    params(("id" = i64, Path, description = "Order id")),
+    request_body = OrderPatchRequest,
+    responses(
+        (status = 200, description = "Order updated"),
+        (status = 404, description = "Unknown customer or product"),
+        (status = 409, description = "A product is not published", body = String, content_type = "text/plain"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::patch("/order/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(order_id = %path))]
+pub async fn patch_order(
+    path: Path<i64>,
+    data: Data<AppData>,
+    body: Json<OrderPatchRequest>,
+) -> Result<HttpResponse> {
+    with_pool!(&data.db_pool, |pool| {
+        let mut transaction = pool.begin().await?;
+        service::order::patch_order(&mut *transaction, path.into_inner(), &body).await?;
+        transaction.commit().await?;
+    });
 
     Ok(HttpResponse::Ok().finish())
 }
 
-#[actix_web::delete("/api/order/{id:\\d+}")]
+#[utoipa::path(
+    delete,
+    path = "/api/v1/order/{id}",
+    tag = "order",
+    params(("id" = i64, Path, description = "Order id")),
+    responses(
+        (status = 200, description = "Order deleted"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::delete("/order/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(order_id = %path))]
 pub async fn delete_order(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
-    sqlx::query!("DELETE FROM orders WHERE id=?", path.into_inner())
-        .execute(&data.db_pool)
-        .await?;
+    with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .delete_order(path.into_inner())
+            .await?
+    });
 
     Ok(HttpResponse::Ok().finish())
 }
 
-#[actix_web::get("/api/order/{id:\\d+}")]
-pub async fn get_order(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
-    let id = path.into_inner();
-
-    let order_row = sqlx::query!(
-        r#"SELECT customer_id
-           FROM orders
-           WHERE id=?"#,
-        id
-    )
-    .fetch_one(&data.db_pool)
-    .await?;
-
-    let order_item_rows = sqlx::query!(
-        r#"SELECT id, product_id, amount
-           FROM order_items
-           WHERE order_id=?"#,
-        id
+#[utoipa::path(
+    get,
+    path = "/api/v1/order/{id}",
+    tag = "order",
+    params(("id" = i64, Path, description = "Order id")),
+    responses(
+        (status = 200, description = "The order", body = OrderResponse),
+        (status = 404, description = "Not found"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .fetch_all(&data.db_pool)
-    .await?;
-
-    let order_items = order_item_rows
-        .iter()
-        .map(|row| OrderItemResponse {
-            id: row.id,
-            product_id: row.product_id,
-            amount: row.amount,
-        })
-        .collect::<Vec<_>>();
-
-    let order = OrderResponse {
-        id,
-        customer_id: order_row.customer_id,
-        items: order_items,
-    };
+)]
+#[actix_web::get("/order/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(order_id = %path))]
+pub async fn get_order(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
+    let order = with_pool!(&data.db_pool, |pool| {
+        pool.acquire().await?.get_order(path.into_inner()).await?
+    });
 
     Ok(HttpResponse::Ok().json(order))
 }
 
-#[actix_web::get("/api/order")]
+#[utoipa::path(
+    get,
+    path = "/api/v1/order",
+    tag = "order",
+    responses(
+        (status = 200, description = "All orders", body = Vec<OrderResponse>),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::get("/order")]
+#[tracing::instrument(skip_all)]
 pub async fn get_orders(data: Data<AppData>) -> Result<HttpResponse> {
-    let rows = sqlx::query!("SELECT id, customer_id FROM orders")
-        .fetch_all(&data.db_pool)
-        .await?;
-
-    let mut orders = Vec::new();
-
-    for row in rows.iter() {
-        let item_rows = sqlx::query!(
-            r#"SELECT id, product_id, amount
-               FROM order_items
-               WHERE order_id=?"#,
-            row.id
-        )
-        .fetch_all(&data.db_pool)
-        .await?;
-
-        let items = item_rows
-            .iter()
-            .map(|row| OrderItemResponse {
-                id: row.id,
-                product_id: row.product_id,
-                amount: row.amount,
-            })
-            .collect::<Vec<_>>();
-
-        orders.push(OrderResponse {
-            id: row.id,
-            customer_id: row.customer_id,
-            items,
-        });
-    }
+    let orders = with_pool!(&data.db_pool, |pool| {
+        pool.acquire().await?.get_orders().await?
+    });
 
     Ok(HttpResponse::Ok().json(orders))
 }
 
-#[actix_web::get("/api/order/customer/{customer_id:\\d+}")]
-pub async fn get_orders_by_customer(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
-    let customer_id = path.into_inner();
-
-    let rows = sqlx::query!(
-        r#"SELECT id
-           FROM orders
-           WHERE customer_id=?"#,
-        customer_id
+#[utoipa::path(
+    get,
+    path = "/api/v1/order/customer/{customer_id}",
+    tag = "order",
+    params(("customer_id" = i64, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "Orders of the customer", body = Vec<OrderResponse>),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .fetch_all(&data.db_pool)
-    .await?;
-
-    let mut orders = Vec::new();
-
-    for row in rows.iter() {
-        let item_rows = sqlx::query!(
-            r#"SELECT id, product_id, amount
-               FROM order_items
-               WHERE order_id=?"#,
-            row.id
-        )
-        .fetch_all(&data.db_pool)
-        .await?;
-
-        let items = item_rows
-            .iter()
-            .map(|row| OrderItemResponse {
-                id: row.id,
-                product_id: row.product_id,
-                amount: row.amount,
-            })
-            .collect::<Vec<_>>();
-
-        orders.push(OrderResponse {
-            id: row.id,
-            customer_id,
-            items,
-        });
-    }
+)]
+#[actix_web::get("/order/customer/{customer_id:\\d+}")]
+#[tracing::instrument(skip_all, fields(customer_id = %path))]
+pub async fn get_orders_by_customer(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
+    let orders = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .get_orders_by_customer(path.into_inner())
+            .await?
+    });
 
     Ok(HttpResponse::Ok().json(orders))
 }
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(create_order)
+        .service(update_order)
+        .service(patch_order)
+        .service(delete_order)
+        .service(get_order)
+        .service(get_orders)
+        .service(get_orders_by_customer);
+}
@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use actix_web::{
+    HttpResponse,
+    web::{Data, Path, Query, ServiceConfig},
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use tokio::sync::watch;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{AppData, Result, db::with_pool, mailer::Email, repository::OutboxRepo};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_ATTEMPTS: i32 = 8;
+const BASE_BACKOFF_SECS: i64 = 30;
+const MAX_BACKOFF_SECS: i64 = 60 * 60;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EmailOutboxQuery {
+    /// pending, sent or dead
+    pub status: Option<String>,
+}
+
+#[derive(FromRow, Serialize, ToSchema)]
+pub struct EmailOutboxResponse {
+    pub id: i64,
+    pub recipient: String,
+    pub subject: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub sent_at: Option<NaiveDateTime>,
+}
+
+/// An email taken off the queue for delivery.
+#[derive(FromRow)]
+pub struct QueuedEmail {
+    pub id: i64,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub text_body: Option<String>,
+    pub attempts: i32,
+}
+
+/// Runs until `shutdown` flips to true. An email being delivered at that point is finished
+/// first, so nothing is left half sent.
+pub async fn run_worker(data: AppData, mut shutdown: watch::Receiver<bool>) {
+    while !*shutdown.borrow() {
+        match deliver_next(&data).await {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(error) => tracing::error!(
+                error = %crate::logging::redact_emails(&error.to_string()),
+                "email outbox worker failed"
+            ),
+        }
+
+        tokio::select! {
+            _ = actix_web::rt::time::sleep(POLL_INTERVAL) => {}
+            _ = shutdown.changed() => {}
+        }
+    }
+
+    tracing::info!("email outbox worker stopped");
+}
+
+/// Delivers the oldest due email, returning whether there was one. On MySQL and PostgreSQL the
+/// row stays locked while it is being sent, so several instances can run the worker against the
+/// same database.
+pub async fn deliver_next(data: &AppData) -> Result<bool> {
+    with_pool!(&data.db_pool, |pool| {
+        let mut transaction = pool.begin().await?;
+
+        let Some(email) = transaction.lock_next_due_email().await? else {
+            return Ok(false);
+        };
+
+        let (id, attempts) = (email.id, email.attempts + 1);
+
+        match send(data, email).await {
+            None => {
+                tracing::info!(email_id = id, attempts, "email delivered");
+                data.metrics.email_sent();
+
+                transaction.mark_email_sent(id, attempts).await?;
+            }
+            Some(error_message) => {
+                let status = if attempts >= MAX_ATTEMPTS {
+                    "dead"
+                } else {
+                    "pending"
+                };
+                let backoff_secs =
+                    (BASE_BACKOFF_SECS << (attempts - 1).min(16)).min(MAX_BACKOFF_SECS);
+
+                tracing::warn!(
+                    email_id = id,
+                    attempts,
+                    status,
+                    retry_in_secs = backoff_secs,
+                    error = %crate::logging::redact_emails(&error_message),
+                    "email delivery failed"
+                );
+                data.metrics.email_failed();
+
+                transaction
+                    .mark_email_failed(id, status, attempts, &error_message, backoff_secs)
+                    .await?;
+            }
+        }
+
+        transaction.commit().await?;
+    });
+
+    Ok(true)
+}
+
+/// Sends `email` through the mailer, returning the error message when it fails.
+async fn send(data: &AppData, email: QueuedEmail) -> Option<String> {
+    let mailer = data.mailer.clone();
+
+    // Mailers are blocking, keep them off the async workers
+    let send_result = actix_web::rt::task::spawn_blocking(move || {
+        mailer.send(&Email {
+            to: email.recipient,
+            subject: email.subject,
+            text_body: email
+                .text_body
+                .unwrap_or_else(|| crate::templates::html_to_text(&email.body)),
+            html_body: email.body,
+        })
+    })
+    .await;
+
+    match send_result {
+        Ok(Ok(())) => None,
+        Ok(Err(error)) => Some(error.to_string()),
+        Err(error) => Some(error.to_string()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/email-outbox",
+    tag = "admin",
+    params(EmailOutboxQuery),
+    security(("admin_auth" = [])),
+    responses(
+        (status = 200, description = "Queued emails, newest first", body = Vec<EmailOutboxResponse>),
+        (status = 401, description = "Admin credentials missing or wrong", body = String, content_type = "text/plain"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::get("/email-outbox")]
+#[tracing::instrument(skip_all)]
+pub async fn get_outbox_emails(
+    data: Data<AppData>,
+    query: Query<EmailOutboxQuery>,
+) -> Result<HttpResponse> {
+    let emails = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .get_outbox_emails(query.status.as_deref())
+            .await?
+    });
+
+    Ok(HttpResponse::Ok().json(emails))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/email-outbox/{id}/retry",
+    tag = "admin",
+    params(("id" = i64, Path, description = "Email id")),
+    security(("admin_auth" = [])),
+    responses(
+        (status = 200, description = "Email queued for another attempt"),
+        (status = 401, description = "Admin credentials missing or wrong", body = String, content_type = "text/plain"),
+        (status = 404, description = "Not found"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::post("/email-outbox/{id:\\d+}/retry")]
+#[tracing::instrument(skip_all, fields(email_id = %path))]
+pub async fn retry_outbox_email(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
+    let retried = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .retry_outbox_email(path.into_inner())
+            .await?
+    });
+
+    if retried {
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(get_outbox_emails).service(retry_outbox_email);
+}
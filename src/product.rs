@@ -1,132 +1,439 @@
+use std::time::Duration;
+
 use actix_web::{
     HttpResponse,
-    web::{Data, Json, Path},
+    web::{Data, Json, Path, Query, ServiceConfig},
 };
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use sqlx::prelude::FromRow;
+use sqlx::{Database, error::BoxDynError, prelude::FromRow};
+use tokio::sync::watch;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{AppData, Result, db::with_pool, repository::ProductRepo};
+
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Only published products are listed to the public. A draft with a `publish_at` is scheduled.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ProductStatus {
+    #[default]
+    Draft,
+    Published,
+    Archived,
+}
+
+impl ProductStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Published => "published",
+            Self::Archived => "archived",
+        }
+    }
+}
+
+/// Stored as its [`name`](ProductStatus::name) in a string column on every backend.
+impl<DB: Database> sqlx::Type<DB> for ProductStatus
+where
+    str: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <str as sqlx::Type<DB>>::type_info()
+    }
 
-use crate::{AppData, Result};
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <str as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'r, DB: Database> sqlx::Decode<'r, DB> for ProductStatus
+where
+    &'r str: sqlx::Decode<'r, DB>,
+{
+    fn decode(value: DB::ValueRef<'r>) -> std::result::Result<Self, BoxDynError> {
+        match <&str as sqlx::Decode<DB>>::decode(value)? {
+            "draft" => Ok(Self::Draft),
+            "published" => Ok(Self::Published),
+            "archived" => Ok(Self::Archived),
+            other => Err(format!("unknown product status {other:?}").into()),
+        }
+    }
+}
 
-#[derive(Deserialize)]
+/// `publish_at` and `unpublish_at` are in the database clock's time zone, and are cleared once
+/// the scheduler applies them.
+#[derive(Deserialize, ToSchema)]
 pub struct ProductRequest {
     pub name: String,
     pub description: Option<String>,
     pub price: i64,
     pub is_featured: bool,
+    /// Draft when creating, kept as is when replacing
+    pub status: Option<ProductStatus>,
+    pub publish_at: Option<NaiveDateTime>,
+    pub unpublish_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ProductPatchRequest {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "crate::util::deserialize_some")]
+    #[schema(value_type = Option<String>)]
+    pub description: Option<Option<String>>,
+    pub price: Option<i64>,
+    pub is_featured: Option<bool>,
+    pub status: Option<ProductStatus>,
+    #[serde(default, deserialize_with = "crate::util::deserialize_some")]
+    #[schema(value_type = Option<NaiveDateTime>)]
+    pub publish_at: Option<Option<NaiveDateTime>>,
+    #[serde(default, deserialize_with = "crate::util::deserialize_some")]
+    #[schema(value_type = Option<NaiveDateTime>)]
+    pub unpublish_at: Option<Option<NaiveDateTime>>,
 }
 
-#[derive(FromRow, Serialize)]
+#[derive(FromRow, Serialize, ToSchema)]
 pub struct ProductResponse {
     pub id: i64,
     pub name: String,
     pub description: Option<String>,
     pub price: i64,
     pub is_featured: bool,
+    pub status: ProductStatus,
+    pub publish_at: Option<NaiveDateTime>,
+    pub unpublish_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProductStatusQuery {
+    pub status: Option<ProductStatus>,
+}
+
+/// Applies the due `publish_at` and `unpublish_at` every [`SCHEDULE_INTERVAL`] until
+/// `shutdown` flips to true.
+pub async fn run_scheduler(data: AppData, mut shutdown: watch::Receiver<bool>) {
+    while !*shutdown.borrow() {
+        if let Err(error) = apply_schedules(&data).await {
+            tracing::error!(%error, "product scheduler failed");
+        }
+
+        tokio::select! {
+            _ = actix_web::rt::time::sleep(SCHEDULE_INTERVAL) => {}
+            _ = shutdown.changed() => {}
+        }
+    }
+
+    tracing::info!("product scheduler stopped");
+}
+
+/// Publishes and then unpublishes the products that are due, so a product whose whole window
+/// has passed ends up archived.
+pub async fn apply_schedules(data: &AppData) -> Result<()> {
+    let (published, unpublished) = with_pool!(&data.db_pool, |pool| {
+        let mut connection = pool.acquire().await?;
+
+        let published = connection.publish_due_products().await?;
+        let unpublished = connection.unpublish_due_products().await?;
+
+        (published, unpublished)
+    });
+
+    if published > 0 || unpublished > 0 {
+        tracing::info!(published, unpublished, "applied product schedules");
+    }
+
+    Ok(())
 }
 
-#[actix_web::post("/api/product")]
+#[utoipa::path(
+    post,
+    path = "/api/v1/product",
+    tag = "product",
+    request_body = ProductRequest,
+    responses(
+        (status = 200, description = "Product created, as a draft unless a status is given"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::post("/product")]
+#[tracing::instrument(skip_all, fields(product_id = tracing::field::Empty))]
 pub async fn create_product(
     data: Data<AppData>,
     body: Json<ProductRequest>,
 ) -> Result<HttpResponse> {
-    sqlx::query!(
-        r#"INSERT INTO products (name, description, price, is_featured)
-           VALUES (?, ?, ?, ?)"#,
-        body.name,
-        body.description,
-        body.price,
-        body.is_featured
-    )
-    .execute(&data.db_pool)
-    .await?;
+    let product_id = with_pool!(&data.db_pool, |pool| {
+        pool.acquire().await?.insert_product(&body).await?
+    });
+
+    tracing::Span::current().record("product_id", product_id);
 
     Ok(HttpResponse::Ok().finish())
 }
 
-#[actix_web::put("/api/product/{id}")]
+#[utoipa::path(
+    put,
+    path = "/api/v1/product/{id}",
+    tag = "product",
+    params(("id" = i64, Path, description = "Product id")),
+    request_body = ProductRequest,
+    responses(
+        (status = 200, description = "Product replaced"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::put("/product/{id}")]
+#[tracing::instrument(skip_all, fields(product_id = %path))]
 pub async fn update_product(
     path: Path<i64>,
     data: Data<AppData>,
     body: Json<ProductRequest>,
 ) -> Result<HttpResponse> {
-    sqlx::query!(
-        r#"UPDATE products
-           SET name=?, description=?, price=?, is_featured=?
-           WHERE id=?"#,
-        body.name,
-        body.description,
-        body.price,
-        body.is_featured,
-        path.into_inner()
+    with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .update_product(path.into_inner(), &body)
+            .await?
+    });
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/product/{id}",
+    tag = "product",
+    params(("id" = i64, Path, description = "Product id")),
+    request_body = ProductPatchRequest,
+    responses(
+        (status = 200, description = "Product updated"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .execute(&data.db_pool)
-    .await?;
+)]
+#[actix_web::patch("/product/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(product_id = %path))]
+pub async fn patch_product(
+    path: Path<i64>,
+    data: Data<AppData>,
+    body: Json<ProductPatchRequest>,
+) -> Result<HttpResponse> {
+    with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .patch_product(path.into_inner(), &body)
+            .await?
+    });
 
     Ok(HttpResponse::Ok().finish())
 }
 
-#[actix_web::delete("/api/product/{id:\\d+}")]
+#[utoipa::path(
+    delete,
+    path = "/api/v1/product/{id}",
+    tag = "product",
+    params(("id" = i64, Path, description = "Product id")),
+    responses(
+        (status = 200, description = "Product hidden until restored or purged"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::delete("/product/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(product_id = %path))]
 pub async fn delete_product(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
-    sqlx::query!("DELETE FROM products WHERE id=?", path.into_inner())
-        .execute(&data.db_pool)
-        .await?;
+    with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .delete_product(path.into_inner())
+            .await?
+    });
 
     Ok(HttpResponse::Ok().finish())
 }
 
-#[actix_web::get("/api/product/{id:\\d+}")]
-pub async fn get_product(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
-    let product = sqlx::query_as!(
-        ProductResponse,
-        r#"SELECT id, name, description, price, is_featured as `is_featured!: bool`
-           FROM products WHERE id=?"#,
-        path.into_inner()
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/product/{id}/restore",
+    tag = "admin",
+    params(("id" = i64, Path, description = "Product id")),
+    security(("admin_auth" = [])),
+    responses(
+        (status = 200, description = "Product restored"),
+        (status = 401, description = "Admin credentials missing or wrong", body = String, content_type = "text/plain"),
+        (status = 404, description = "Not found or not deleted"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::post("/product/{id:\\d+}/restore")]
+#[tracing::instrument(skip_all, fields(product_id = %path))]
+pub async fn restore_product(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
+    let restored = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .restore_product(path.into_inner())
+            .await?
+    });
+
+    if restored {
+        Ok(HttpResponse::Ok().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/product/{id}",
+    tag = "product",
+    params(("id" = i64, Path, description = "Product id")),
+    responses(
+        (status = 200, description = "The product", body = ProductResponse),
+        (status = 404, description = "Not found or not published"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .fetch_one(&data.db_pool)
-    .await?;
+)]
+#[actix_web::get("/product/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(product_id = %path))]
+pub async fn get_product(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
+    let product = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .get_published_product(path.into_inner())
+            .await?
+    });
 
     Ok(HttpResponse::Ok().json(product))
 }
 
-#[actix_web::get("/api/product")]
-pub async fn get_products(data: Data<AppData>) -> Result<HttpResponse> {
-    let products = sqlx::query_as!(
-        ProductResponse,
-        r#"SELECT id, name, description, price, is_featured as `is_featured!: bool`
-           FROM products"#
+#[utoipa::path(
+    get,
+    path = "/api/v1/product",
+    tag = "product",
+    responses(
+        (status = 200, description = "Published products", body = Vec<ProductResponse>),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .fetch_all(&data.db_pool)
-    .await?;
+)]
+#[actix_web::get("/product")]
+#[tracing::instrument(skip_all)]
+pub async fn get_products(data: Data<AppData>) -> Result<HttpResponse> {
+    let products = with_pool!(&data.db_pool, |pool| {
+        pool.acquire().await?.get_products().await?
+    });
 
     Ok(HttpResponse::Ok().json(products))
 }
 
-#[actix_web::get("/api/product/featured")]
-pub async fn get_featured_products(data: Data<AppData>) -> Result<HttpResponse> {
-    let products = sqlx::query_as!(
-        ProductResponse,
-        r#"SELECT id, name, description, price, is_featured as `is_featured: bool`
-        FROM products WHERE is_featured=TRUE"#
+#[utoipa::path(
+    get,
+    path = "/api/v1/product/featured",
+    tag = "product",
+    responses(
+        (status = 200, description = "Published featured products", body = Vec<ProductResponse>),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .fetch_all(&data.db_pool)
-    .await?;
+)]
+#[actix_web::get("/product/featured")]
+#[tracing::instrument(skip_all)]
+pub async fn get_featured_products(data: Data<AppData>) -> Result<HttpResponse> {
+    let products = with_pool!(&data.db_pool, |pool| {
+        pool.acquire().await?.get_featured_products().await?
+    });
 
     Ok(HttpResponse::Ok().json(products))
 }
 
-#[actix_web::get("/api/product/search/{term}")]
+#[utoipa::path(
+    get,
+    path = "/api/v1/product/search/{term}",
+    tag = "product",
+    params(("term" = String, Path, description = "Text to look for in the name")),
+    responses(
+        (status = 200, description = "Matching published products", body = Vec<ProductResponse>),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::get("/product/search/{term}")]
+#[tracing::instrument(skip_all)]
 pub async fn get_products_with_search(
     path: Path<String>,
     data: Data<AppData>,
 ) -> Result<HttpResponse> {
-    let products = sqlx::query_as!(
-        ProductResponse,
-        r#"SELECT id, name, description, price, is_featured as `is_featured!: bool`
-           FROM products WHERE name LIKE ?"#,
-        path.into_inner()
+    let products = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .search_products(&path.into_inner())
+            .await?
+    });
+
+    Ok(HttpResponse::Ok().json(products))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/product/{id}",
+    tag = "admin",
+    params(("id" = i64, Path, description = "Product id")),
+    security(("admin_auth" = [])),
+    responses(
+        (status = 200, description = "The product, in any status", body = ProductResponse),
+        (status = 401, description = "Admin credentials missing or wrong", body = String, content_type = "text/plain"),
+        (status = 404, description = "Not found"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
     )
-    .fetch_all(&data.db_pool)
-    .await?;
+)]
+#[actix_web::get("/product/{id:\\d+}")]
+#[tracing::instrument(skip_all, fields(product_id = %path))]
+pub async fn get_any_product(path: Path<i64>, data: Data<AppData>) -> Result<HttpResponse> {
+    let product = with_pool!(&data.db_pool, |pool| {
+        pool.acquire().await?.get_product(path.into_inner()).await?
+    });
+
+    Ok(HttpResponse::Ok().json(product))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/product",
+    tag = "admin",
+    params(ProductStatusQuery),
+    security(("admin_auth" = [])),
+    responses(
+        (status = 200, description = "Products in any status, or in the given one", body = Vec<ProductResponse>),
+        (status = 401, description = "Admin credentials missing or wrong", body = String, content_type = "text/plain"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::get("/product")]
+#[tracing::instrument(skip_all)]
+pub async fn get_products_by_status(
+    data: Data<AppData>,
+    query: Query<ProductStatusQuery>,
+) -> Result<HttpResponse> {
+    let products = with_pool!(&data.db_pool, |pool| {
+        pool.acquire()
+            .await?
+            .get_products_by_status(query.status)
+            .await?
+    });
 
     Ok(HttpResponse::Ok().json(products))
 }
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(create_product)
+        .service(update_product)
+        .service(patch_product)
+        .service(delete_product)
+        .service(get_product)
+        .service(get_products)
+        .service(get_featured_products)
+        .service(get_products_with_search);
+}
+
+/// Routes mounted under `/admin`.
+pub fn configure_admin(cfg: &mut ServiceConfig) {
+    cfg.service(get_any_product)
+        .service(get_products_by_status)
+        .service(restore_product);
+}
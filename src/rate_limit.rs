@@ -0,0 +1,387 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::Method,
+    middleware::Next,
+    web::{Bytes, Data},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppData, Config, Result,
+    db::{DbPool, with_pool},
+    error::Error,
+    repository::RateLimitRepo,
+};
+
+/// How often the in-memory store drops the buckets that are full again.
+const MEMORY_EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+/// One in this many database takes also deletes the buckets untouched for a day.
+const DATABASE_PURGE_ODDS: u32 = 1000;
+
+/// Routes that are rate limited, by method and unversioned route pattern, and the group whose
+/// policies apply to them.
+const ROUTE_GROUPS: &[(Method, &str, &str)] = &[
+    (Method::POST, "/api/customer", "signup"),
+    (Method::GET, "/api/customer/login", "login"),
+    (Method::POST, "/api/customer/login", "login"),
+    (
+        Method::GET,
+        "/api/customer/reset-password/{email}",
+        "password_reset",
+    ),
+    (
+        Method::POST,
+        "/api/customer/{id:\\d+}/email",
+        "email_change",
+    ),
+    (
+        Method::PUT,
+        "/api/customer/{id:\\d+}/password",
+        "password_change",
+    ),
+];
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitStore {
+    /// Per instance, lost on restart
+    #[default]
+    Memory,
+    /// Shared by every instance using the same database
+    Database,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitKey {
+    /// Client IP address
+    Ip,
+    /// Customer id in the path, falling back to the IP
+    Customer,
+    /// Email address in the path or the JSON body
+    Email,
+}
+
+impl RateLimitKey {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Ip => "ip",
+            Self::Customer => "customer",
+            Self::Email => "email",
+        }
+    }
+}
+
+/// Token bucket holding up to `capacity` requests, one coming back every `refill_secs`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RateLimitPolicy {
+    pub key: RateLimitKey,
+    pub capacity: u32,
+    pub refill_secs: u64,
+}
+
+impl RateLimitPolicy {
+    const fn new(key: RateLimitKey, capacity: u32, refill_secs: u64) -> Self {
+        Self {
+            key,
+            capacity,
+            refill_secs,
+        }
+    }
+
+    /// Refills `tokens` for the `elapsed_secs` since the last update and takes one. Returns
+    /// the tokens left, or the seconds until one is available.
+    fn take(&self, tokens: f64, elapsed_secs: f64) -> std::result::Result<f64, u64> {
+        let capacity = f64::from(self.capacity);
+        let refill_secs = self.refill_secs.max(1) as f64;
+        let tokens = (tokens + elapsed_secs.max(0.0) / refill_secs).min(capacity);
+
+        if tokens >= 1.0 {
+            Ok(tokens - 1.0)
+        } else {
+            Err(((1.0 - tokens) * refill_secs).ceil() as u64)
+        }
+    }
+}
+
+pub fn default_policies() -> BTreeMap<String, Vec<RateLimitPolicy>> {
+    use RateLimitKey::*;
+
+    BTreeMap::from([
+        ("signup".to_owned(), vec![RateLimitPolicy::new(Ip, 5, 720)]),
+        (
+            "login".to_owned(),
+            vec![
+                RateLimitPolicy::new(Ip, 20, 6),
+                RateLimitPolicy::new(Email, 5, 60),
+            ],
+        ),
+        (
+            "password_reset".to_owned(),
+            vec![
+                RateLimitPolicy::new(Email, 3, 1200),
+                RateLimitPolicy::new(Ip, 10, 360),
+            ],
+        ),
+        (
+            "email_change".to_owned(),
+            vec![RateLimitPolicy::new(Customer, 3, 1200)],
+        ),
+        (
+            "password_change".to_owned(),
+            vec![RateLimitPolicy::new(Customer, 5, 60)],
+        ),
+    ])
+}
+
+struct MemoryBucket {
+    tokens: f64,
+    updated_at: Instant,
+    /// From then on the bucket is full again and can be forgotten
+    full_at: Instant,
+}
+
+struct MemoryStore {
+    buckets: HashMap<String, MemoryBucket>,
+    evicted_at: Instant,
+}
+
+enum Store {
+    Memory(Mutex<MemoryStore>),
+    Database(DbPool),
+}
+
+/// A bucket to take a token from, under the policy it belongs to.
+type BucketRef<'a> = (String, &'a RateLimitPolicy);
+
+pub struct RateLimiter {
+    store: Store,
+}
+
+impl RateLimiter {
+    pub fn from_config(config: &Config, db_pool: &DbPool) -> Self {
+        let store = match config.rate_limit_store {
+            RateLimitStore::Memory => Store::Memory(Mutex::new(MemoryStore {
+                buckets: HashMap::new(),
+                evicted_at: Instant::now(),
+            })),
+            RateLimitStore::Database => Store::Database(db_pool.clone()),
+        };
+
+        Self { store }
+    }
+
+    /// Takes a token from every bucket, or from none of them when any is empty. Returns the key
+    /// of the policy that waits the longest and the seconds to wait in that case.
+    async fn take(&self, buckets: &[BucketRef<'_>]) -> Result<Option<(RateLimitKey, u64)>> {
+        match &self.store {
+            Store::Memory(store) => {
+                let mut store = store.lock().unwrap();
+                let now = Instant::now();
+
+                if now.duration_since(store.evicted_at) >= MEMORY_EVICTION_INTERVAL {
+                    store.buckets.retain(|_, bucket| bucket.full_at > now);
+                    store.evicted_at = now;
+                }
+
+                let outcomes: Vec<_> = buckets
+                    .iter()
+                    .map(|(bucket_key, policy)| match store.buckets.get(bucket_key) {
+                        Some(bucket) => policy.take(
+                            bucket.tokens,
+                            now.duration_since(bucket.updated_at).as_secs_f64(),
+                        ),
+                        None => policy.take(f64::from(policy.capacity), 0.0),
+                    })
+                    .collect();
+
+                if let Some(rejection) = longest_wait(buckets, &outcomes) {
+                    return Ok(Some(rejection));
+                }
+
+                for ((bucket_key, policy), outcome) in buckets.iter().zip(outcomes) {
+                    let tokens = outcome.unwrap_or_default();
+                    let missing_tokens = f64::from(policy.capacity) - tokens;
+                    store.buckets.insert(
+                        bucket_key.clone(),
+                        MemoryBucket {
+                            tokens,
+                            updated_at: now,
+                            full_at: now
+                                + Duration::from_secs_f64(
+                                    missing_tokens * policy.refill_secs as f64,
+                                ),
+                        },
+                    );
+                }
+
+                Ok(None)
+            }
+            Store::Database(db_pool) => take_from_database(db_pool, buckets).await,
+        }
+    }
+}
+
+/// The rejecting policy with the longest wait, if any policy rejects.
+fn longest_wait(
+    buckets: &[BucketRef<'_>],
+    outcomes: &[std::result::Result<f64, u64>],
+) -> Option<(RateLimitKey, u64)> {
+    buckets
+        .iter()
+        .zip(outcomes)
+        .filter_map(|((_, policy), outcome)| {
+            outcome
+                .err()
+                .map(|retry_after_secs| (policy.key, retry_after_secs))
+        })
+        .max_by_key(|(_, retry_after_secs)| *retry_after_secs)
+}
+
+/// Same as the in-memory store, with the buckets locked for the read-modify-write. Buckets are
+/// always locked in policy order, so concurrent requests don't deadlock.
+async fn take_from_database(
+    db_pool: &DbPool,
+    buckets: &[BucketRef<'_>],
+) -> Result<Option<(RateLimitKey, u64)>> {
+    with_pool!(db_pool, |pool| {
+        let mut transaction = pool.begin().await?;
+
+        let mut outcomes = Vec::new();
+        for (bucket_key, policy) in buckets {
+            let (tokens, elapsed_secs) = transaction
+                .lock_rate_limit_bucket(bucket_key, f64::from(policy.capacity))
+                .await?;
+            outcomes.push(policy.take(tokens, elapsed_secs));
+        }
+
+        let maybe_rejection = longest_wait(buckets, &outcomes);
+        if maybe_rejection.is_none() {
+            for ((bucket_key, _), outcome) in buckets.iter().zip(outcomes) {
+                transaction
+                    .update_rate_limit_bucket(bucket_key, outcome.unwrap_or_default())
+                    .await?;
+            }
+        }
+
+        transaction.commit().await?;
+
+        if rand::random_ratio(1, DATABASE_PURGE_ODDS) {
+            pool.acquire().await?.purge_rate_limit_buckets().await?;
+        }
+
+        Ok(maybe_rejection)
+    })
+}
+
+#[derive(Deserialize)]
+struct EmailBody {
+    email: String,
+}
+
+/// Applies the policies of the route's group, rejecting with 429 and `Retry-After` once any of
+/// its buckets is empty. Store failures let the request through rather than locking users out.
+pub async fn limit_requests(
+    mut request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> std::result::Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let maybe_group = request.match_pattern().and_then(|pattern| {
+        let pattern = crate::versioning::unversioned_path(&pattern);
+        ROUTE_GROUPS
+            .iter()
+            .find(|(method, route, _)| method == request.method() && *route == pattern)
+            .map(|(_, _, group)| *group)
+    });
+    let maybe_data = request.app_data::<Data<AppData>>().cloned();
+
+    let (Some(group), Some(data)) = (maybe_group, maybe_data) else {
+        return Ok(next.call(request).await?.map_into_left_body());
+    };
+    let Some(policies) = data.config.rate_limits.get(group) else {
+        return Ok(next.call(request).await?.map_into_left_body());
+    };
+
+    let needs_body_email = policies
+        .iter()
+        .any(|policy| policy.key == RateLimitKey::Email)
+        && request.match_info().get("email").is_none();
+    let body_email = if needs_body_email {
+        // Read the body to find the email, then put it back for the handler
+        let body = request.extract::<Bytes>().await?;
+        let email = serde_json::from_slice::<EmailBody>(&body)
+            .ok()
+            .map(|body| body.email);
+        request.set_payload(body.into());
+        email
+    } else {
+        None
+    };
+
+    let ip = client_ip(&request, data.config.rate_limit_trust_proxy);
+
+    let mut buckets = Vec::new();
+    for policy in policies {
+        let key_value = match policy.key {
+            RateLimitKey::Ip => ip.clone(),
+            RateLimitKey::Customer => request
+                .match_info()
+                .get("id")
+                .map(str::to_owned)
+                .unwrap_or_else(|| ip.clone()),
+            RateLimitKey::Email => {
+                let maybe_email = request
+                    .match_info()
+                    .get("email")
+                    .map(str::to_owned)
+                    .or_else(|| body_email.clone());
+                match maybe_email {
+                    Some(email) => email.trim().to_lowercase(),
+                    // Nothing to key on, the handler rejects the request anyway
+                    None => continue,
+                }
+            }
+        };
+
+        // Hashed so the database store doesn't keep emails and IPs around
+        let bucket_key = format!(
+            "{group}:{}:{}",
+            policy.key.name(),
+            crate::util::hash(&key_value)
+        );
+        buckets.push((bucket_key, policy));
+    }
+
+    match data.rate_limiter.take(&buckets).await {
+        Ok(None) => {}
+        Ok(Some((key, retry_after_secs))) => {
+            tracing::warn!(group, key = key.name(), "rate limited");
+            // A response rather than an error, so the outer middleware still handles it
+            return Ok(request
+                .error_response(Error::RateLimitError { retry_after_secs })
+                .map_into_right_body());
+        }
+        Err(error) => {
+            tracing::error!(%error, "rate limit store failed");
+        }
+    }
+
+    Ok(next.call(request).await?.map_into_left_body())
+}
+
+fn client_ip(request: &ServiceRequest, trust_proxy: bool) -> String {
+    let connection_info = request.connection_info();
+    let maybe_ip = if trust_proxy {
+        connection_info.realip_remote_addr()
+    } else {
+        connection_info.peer_addr()
+    };
+
+    maybe_ip.unwrap_or("unknown").to_owned()
+}
@@ -0,0 +1,311 @@
+//! Queries per entity, behind traits implemented by the connection type of each backend. Methods
+//! take the connection as `&mut self`, so they run on a pooled connection or, through deref, on a
+//! transaction shared with other repositories:
+//!
+//! ```ignore
+//! let mut transaction = pool.begin().await?;
+//! let customer_id = transaction.insert_customer(&body, &hashed_password).await?;
+//! transaction.enqueue_email(&message).await?;
+//! transaction.commit().await?;
+//! ```
+
+use std::future::Future;
+
+use crate::{
+    Result,
+    customer::{CustomerPatchRequest, CustomerRequest, CustomerResponse},
+    mailer::Email,
+    order::{OrderItemRequest, OrderResponse},
+    outbox::{EmailOutboxResponse, QueuedEmail},
+    product::{ProductPatchRequest, ProductRequest, ProductResponse, ProductStatus},
+};
+
+mod mysql;
+mod postgres;
+mod sqlite;
+
+/// Deleted products stay in the orders that have them, and are left out everywhere else.
+pub trait ProductRepo {
+    fn insert_product(
+        &mut self,
+        product: &ProductRequest,
+    ) -> impl Future<Output = Result<i64>> + Send;
+
+    fn update_product(
+        &mut self,
+        id: i64,
+        product: &ProductRequest,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Only the fields that are present are changed; a `Some(None)` description clears it.
+    fn patch_product(
+        &mut self,
+        id: i64,
+        patch: &ProductPatchRequest,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Marks the product deleted, keeping it until [`ProductRepo::purge_products`].
+    fn delete_product(&mut self, id: i64) -> impl Future<Output = Result<()>> + Send;
+
+    /// Undoes [`ProductRepo::delete_product`]. Returns whether the product was deleted.
+    fn restore_product(&mut self, id: i64) -> impl Future<Output = Result<bool>> + Send;
+
+    /// Removes the products deleted more than `retention_days` ago that no order has. Returns
+    /// how many there were.
+    fn purge_products(&mut self, retention_days: i64) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Fails with `RowNotFound` when there is no such product, or it is deleted. Any status is
+    /// returned.
+    fn get_product(&mut self, id: i64) -> impl Future<Output = Result<ProductResponse>> + Send;
+
+    /// Like [`ProductRepo::get_product`], but fails with `RowNotFound` unless it is published.
+    fn get_published_product(
+        &mut self,
+        id: i64,
+    ) -> impl Future<Output = Result<ProductResponse>> + Send;
+
+    /// Published products.
+    fn get_products(&mut self) -> impl Future<Output = Result<Vec<ProductResponse>>> + Send;
+
+    /// Published featured products.
+    fn get_featured_products(
+        &mut self,
+    ) -> impl Future<Output = Result<Vec<ProductResponse>>> + Send;
+
+    /// Products in `status`, or in any status when `None`.
+    fn get_products_by_status(
+        &mut self,
+        status: Option<ProductStatus>,
+    ) -> impl Future<Output = Result<Vec<ProductResponse>>> + Send;
+
+    /// Publishes the drafts whose `publish_at` has passed, clearing it. Returns how many there
+    /// were.
+    fn publish_due_products(&mut self) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Archives the published products whose `unpublish_at` has passed, clearing it. Returns how
+    /// many there were.
+    fn unpublish_due_products(&mut self) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Published products whose name matches the `LIKE` pattern `term`.
+    fn search_products(
+        &mut self,
+        term: &str,
+    ) -> impl Future<Output = Result<Vec<ProductResponse>>> + Send;
+
+    fn find_product_id_by_name(
+        &mut self,
+        name: &str,
+    ) -> impl Future<Output = Result<Option<i64>>> + Send;
+}
+
+/// Deleted customers keep their orders and their email, which cannot be registered again until
+/// they are purged. They are left out everywhere else.
+pub trait CustomerRepo {
+    /// Stores `customer` with `hashed_password` in place of its plain password.
+    fn insert_customer(
+        &mut self,
+        customer: &CustomerRequest,
+        hashed_password: &str,
+    ) -> impl Future<Output = Result<i64>> + Send;
+
+    /// Replaces everything but the email, which has to be confirmed first.
+    fn update_customer(
+        &mut self,
+        id: i64,
+        customer: &CustomerRequest,
+        hashed_password: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Only the fields that are present are changed, the email excepted.
+    fn patch_customer(
+        &mut self,
+        id: i64,
+        patch: &CustomerPatchRequest,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Marks the customer deleted, keeping it until [`CustomerRepo::purge_customers`].
+    fn delete_customer(&mut self, id: i64) -> impl Future<Output = Result<()>> + Send;
+
+    /// Undoes [`CustomerRepo::delete_customer`]. Returns whether the customer was deleted.
+    fn restore_customer(&mut self, id: i64) -> impl Future<Output = Result<bool>> + Send;
+
+    /// Removes the customers deleted more than `retention_days` ago, along with their orders.
+    /// Returns how many there were.
+    fn purge_customers(&mut self, retention_days: i64) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Fails with `RowNotFound` when there is no such customer, or it is deleted.
+    fn get_customer(&mut self, id: i64) -> impl Future<Output = Result<CustomerResponse>> + Send;
+
+    fn get_customers(&mut self) -> impl Future<Output = Result<Vec<CustomerResponse>>> + Send;
+
+    fn find_customer_by_email(
+        &mut self,
+        email: &str,
+    ) -> impl Future<Output = Result<Option<CustomerResponse>>> + Send;
+
+    /// Deleted customers included, since their email stays taken.
+    fn find_customer_id_by_email(
+        &mut self,
+        email: &str,
+    ) -> impl Future<Output = Result<Option<i64>>> + Send;
+
+    /// The active customer with these credentials, if any.
+    fn find_customer_by_login(
+        &mut self,
+        email: &str,
+        hashed_password: &str,
+    ) -> impl Future<Output = Result<Option<CustomerResponse>>> + Send;
+
+    /// Id of the active admin with these credentials, if any.
+    fn find_admin_id_by_login(
+        &mut self,
+        email: &str,
+        hashed_password: &str,
+    ) -> impl Future<Output = Result<Option<i64>>> + Send;
+
+    fn set_customer_password(
+        &mut self,
+        id: i64,
+        hashed_password: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Stores the token of a password reset link, usable for `ttl_minutes`, replacing the one
+    /// sent before.
+    fn set_password_reset_token(
+        &mut self,
+        id: i64,
+        token: &str,
+        ttl_minutes: i64,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Sets the password of the customer `token` was sent to, unless it expired, after which the
+    /// token is spent. Returns whether there was one.
+    fn reset_customer_password(
+        &mut self,
+        token: &str,
+        hashed_password: &str,
+    ) -> impl Future<Output = Result<bool>> + Send;
+
+    /// Stores the token of the activation link, replacing the one sent before.
+    fn set_activation_token(
+        &mut self,
+        id: i64,
+        token: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Activates the customer `token` was sent to, after which the token is spent. Returns
+    /// whether there was one.
+    fn activate_customer(&mut self, token: &str) -> impl Future<Output = Result<bool>> + Send;
+
+    fn set_pending_email(
+        &mut self,
+        id: i64,
+        pending_email: &str,
+        token: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Moves the pending email matching `token` into place. Returns whether there was one.
+    fn confirm_email_change(&mut self, token: &str) -> impl Future<Output = Result<bool>> + Send;
+
+    fn cancel_email_change(&mut self, id: i64) -> impl Future<Output = Result<()>> + Send;
+
+    /// Drops the pending email matching `token`. Returns whether there was one.
+    fn cancel_email_change_with_token(
+        &mut self,
+        token: &str,
+    ) -> impl Future<Output = Result<bool>> + Send;
+
+    /// Creates the admin account, or resets the name and password of the existing one and
+    /// restores it if deleted.
+    fn upsert_admin(
+        &mut self,
+        name: &str,
+        email: &str,
+        hashed_password: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+}
+
+pub trait OrderRepo {
+    fn insert_order(&mut self, customer_id: i64) -> impl Future<Output = Result<i64>> + Send;
+
+    fn set_order_customer(
+        &mut self,
+        id: i64,
+        customer_id: i64,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Replaces all the items of the order with `items`.
+    fn set_order_items(
+        &mut self,
+        order_id: i64,
+        items: &[OrderItemRequest],
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn delete_order(&mut self, id: i64) -> impl Future<Output = Result<()>> + Send;
+
+    /// Fails with `RowNotFound` when there is no such order.
+    fn get_order(&mut self, id: i64) -> impl Future<Output = Result<OrderResponse>> + Send;
+
+    fn get_orders(&mut self) -> impl Future<Output = Result<Vec<OrderResponse>>> + Send;
+
+    fn get_orders_by_customer(
+        &mut self,
+        customer_id: i64,
+    ) -> impl Future<Output = Result<Vec<OrderResponse>>> + Send;
+}
+
+pub trait OutboxRepo {
+    /// Queues an email for the background worker. Run it on the transaction of the business
+    /// change so both are committed (or rolled back) together.
+    fn enqueue_email(&mut self, email: &Email) -> impl Future<Output = Result<()>> + Send;
+
+    /// The oldest due email, locked until the transaction ends where the backend supports it,
+    /// skipping the ones other workers have locked.
+    fn lock_next_due_email(&mut self) -> impl Future<Output = Result<Option<QueuedEmail>>> + Send;
+
+    fn mark_email_sent(
+        &mut self,
+        id: i64,
+        attempts: i32,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Records a failed attempt, leaving the email in `status` to be retried in `retry_in_secs`.
+    fn mark_email_failed(
+        &mut self,
+        id: i64,
+        status: &str,
+        attempts: i32,
+        error_message: &str,
+        retry_in_secs: i64,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Emails newest first, only those in `status` when given.
+    fn get_outbox_emails(
+        &mut self,
+        status: Option<&str>,
+    ) -> impl Future<Output = Result<Vec<EmailOutboxResponse>>> + Send;
+
+    /// Queues an email that is not sent yet for an immediate attempt. Returns whether there
+    /// was one.
+    fn retry_outbox_email(&mut self, id: i64) -> impl Future<Output = Result<bool>> + Send;
+}
+
+pub trait RateLimitRepo {
+    /// Creates the bucket full with `capacity` tokens when missing, and locks it until the
+    /// transaction ends. Returns its tokens and the seconds since it was last updated, measured
+    /// by the database so instances with skewed clocks agree.
+    fn lock_rate_limit_bucket(
+        &mut self,
+        bucket_key: &str,
+        capacity: f64,
+    ) -> impl Future<Output = Result<(f64, f64)>> + Send;
+
+    fn update_rate_limit_bucket(
+        &mut self,
+        bucket_key: &str,
+        tokens: f64,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Deletes the buckets untouched for a day, which are full again anyway.
+    fn purge_rate_limit_buckets(&mut self) -> impl Future<Output = Result<()>> + Send;
+}
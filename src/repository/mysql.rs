@@ -0,0 +1,869 @@
+use sqlx::MySqlConnection;
+
+use super::{CustomerRepo, OrderRepo, OutboxRepo, ProductRepo, RateLimitRepo};
+use crate::{
+    Result,
+    customer::{CustomerPatchRequest, CustomerRequest, CustomerResponse},
+    mailer::Email,
+    order::{OrderItemRequest, OrderItemResponse, OrderResponse},
+    outbox::{EmailOutboxResponse, QueuedEmail},
+    product::{ProductPatchRequest, ProductRequest, ProductResponse, ProductStatus},
+    templates::Locale,
+};
+
+impl ProductRepo for MySqlConnection {
+    async fn insert_product(&mut self, product: &ProductRequest) -> Result<i64> {
+        let query_result = sqlx::query!(
+            r#"INSERT INTO products
+               (name, description, price, is_featured, status, publish_at, unpublish_at)
+               VALUES (?, ?, ?, ?, ?, ?, ?)"#,
+            product.name,
+            product.description,
+            product.price,
+            product.is_featured,
+            product.status.unwrap_or_default().name(),
+            product.publish_at,
+            product.unpublish_at
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.last_insert_id() as i64)
+    }
+
+    async fn update_product(&mut self, id: i64, product: &ProductRequest) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE products
+               SET name=?, description=?, price=?, is_featured=?,
+                   status=COALESCE(?, status), publish_at=?, unpublish_at=?
+               WHERE id=? AND deleted_at IS NULL"#,
+            product.name,
+            product.description,
+            product.price,
+            product.is_featured,
+            product.status.map(ProductStatus::name),
+            product.publish_at,
+            product.unpublish_at,
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn patch_product(&mut self, id: i64, patch: &ProductPatchRequest) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE products
+               SET name=COALESCE(?, name),
+                   description=IF(?, ?, description),
+                   price=COALESCE(?, price),
+                   is_featured=COALESCE(?, is_featured),
+                   status=COALESCE(?, status),
+                   publish_at=IF(?, ?, publish_at),
+                   unpublish_at=IF(?, ?, unpublish_at)
+               WHERE id=? AND deleted_at IS NULL"#,
+            patch.name,
+            patch.description.is_some(),
+            patch.description.clone().flatten(),
+            patch.price,
+            patch.is_featured,
+            patch.status.map(ProductStatus::name),
+            patch.publish_at.is_some(),
+            patch.publish_at.flatten(),
+            patch.unpublish_at.is_some(),
+            patch.unpublish_at.flatten(),
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_product(&mut self, id: i64) -> Result<()> {
+        sqlx::query!(
+            "UPDATE products SET deleted_at=NOW() WHERE id=? AND deleted_at IS NULL",
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn restore_product(&mut self, id: i64) -> Result<bool> {
+        let query_result = sqlx::query!(
+            "UPDATE products SET deleted_at=NULL WHERE id=? AND deleted_at IS NOT NULL",
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn purge_products(&mut self, retention_days: i64) -> Result<u64> {
+        let query_result = sqlx::query!(
+            r#"DELETE FROM products
+               WHERE deleted_at < NOW() - INTERVAL ? DAY
+                 AND NOT EXISTS (SELECT 1 FROM order_items WHERE product_id=products.id)"#,
+            retention_days
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected())
+    }
+
+    async fn get_product(&mut self, id: i64) -> Result<ProductResponse> {
+        let product = sqlx::query_as!(
+            ProductResponse,
+            r#"SELECT id, name, description, price, is_featured as `is_featured!: bool`,
+                      status as `status: _`, publish_at, unpublish_at
+               FROM products WHERE id=? AND deleted_at IS NULL"#,
+            id
+        )
+        .fetch_one(&mut *self)
+        .await?;
+
+        Ok(product)
+    }
+
+    async fn get_published_product(&mut self, id: i64) -> Result<ProductResponse> {
+        let product = sqlx::query_as!(
+            ProductResponse,
+            r#"SELECT id, name, description, price, is_featured as `is_featured!: bool`,
+                      status as `status: _`, publish_at, unpublish_at
+               FROM products WHERE id=? AND status='published' AND deleted_at IS NULL"#,
+            id
+        )
+        .fetch_one(&mut *self)
+        .await?;
+
+        Ok(product)
+    }
+
+    async fn get_products(&mut self) -> Result<Vec<ProductResponse>> {
+        let products = sqlx::query_as!(
+            ProductResponse,
+            r#"SELECT id, name, description, price, is_featured as `is_featured!: bool`,
+                      status as `status: _`, publish_at, unpublish_at
+               FROM products WHERE status='published' AND deleted_at IS NULL"#
+        )
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(products)
+    }
+
+    async fn get_featured_products(&mut self) -> Result<Vec<ProductResponse>> {
+        let products = sqlx::query_as!(
+            ProductResponse,
+            r#"SELECT id, name, description, price, is_featured as `is_featured!: bool`,
+                      status as `status: _`, publish_at, unpublish_at
+               FROM products
+               WHERE is_featured=TRUE AND status='published' AND deleted_at IS NULL"#
+        )
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(products)
+    }
+
+    async fn get_products_by_status(
+        &mut self,
+        status: Option<ProductStatus>,
+    ) -> Result<Vec<ProductResponse>> {
+        let status = status.map(ProductStatus::name);
+        let products = sqlx::query_as!(
+            ProductResponse,
+            r#"SELECT id, name, description, price, is_featured as `is_featured!: bool`,
+                      status as `status: _`, publish_at, unpublish_at
+               FROM products
+               WHERE (? IS NULL OR status=?) AND deleted_at IS NULL
+               ORDER BY id"#,
+            status,
+            status
+        )
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(products)
+    }
+
+    async fn publish_due_products(&mut self) -> Result<u64> {
+        let query_result = sqlx::query!(
+            r#"UPDATE products SET status='published', publish_at=NULL
+               WHERE status='draft' AND publish_at <= NOW() AND deleted_at IS NULL"#
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected())
+    }
+
+    async fn unpublish_due_products(&mut self) -> Result<u64> {
+        let query_result = sqlx::query!(
+            r#"UPDATE products SET status='archived', unpublish_at=NULL
+               WHERE status='published' AND unpublish_at <= NOW() AND deleted_at IS NULL"#
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected())
+    }
+
+    async fn search_products(&mut self, term: &str) -> Result<Vec<ProductResponse>> {
+        let products = sqlx::query_as!(
+            ProductResponse,
+            r#"SELECT id, name, description, price, is_featured as `is_featured!: bool`,
+                      status as `status: _`, publish_at, unpublish_at
+               FROM products
+               WHERE name LIKE ? AND status='published' AND deleted_at IS NULL"#,
+            term
+        )
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(products)
+    }
+
+    async fn find_product_id_by_name(&mut self, name: &str) -> Result<Option<i64>> {
+        let maybe_id = sqlx::query_scalar!("SELECT id FROM products WHERE name=? LIMIT 1", name)
+            .fetch_optional(&mut *self)
+            .await?;
+
+        Ok(maybe_id)
+    }
+}
+
+impl CustomerRepo for MySqlConnection {
+    async fn insert_customer(
+        &mut self,
+        customer: &CustomerRequest,
+        hashed_password: &str,
+    ) -> Result<i64> {
+        let query_result = sqlx::query!(
+            r#"INSERT INTO customers
+               (name, email, password, phone_number, address, is_active, preferred_locale)
+               VALUES (?, ?, ?, ?, ?, ?, ?)"#,
+            customer.name,
+            customer.email,
+            hashed_password,
+            customer.phone_number,
+            customer.address,
+            customer.is_active.unwrap_or(false),
+            customer.preferred_locale.unwrap_or_default().tag(),
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.last_insert_id() as i64)
+    }
+
+    async fn update_customer(
+        &mut self,
+        id: i64,
+        customer: &CustomerRequest,
+        hashed_password: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE customers
+               SET name=?, password=?, phone_number=?, address=?,
+                   is_active=COALESCE(?, is_active),
+                   preferred_locale=COALESCE(?, preferred_locale)
+               WHERE id=? AND deleted_at IS NULL"#,
+            customer.name,
+            hashed_password,
+            customer.phone_number,
+            customer.address,
+            customer.is_active,
+            customer.preferred_locale.map(Locale::tag),
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn patch_customer(&mut self, id: i64, patch: &CustomerPatchRequest) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE customers
+               SET name=COALESCE(?, name),
+                   phone_number=IF(?, ?, phone_number),
+                   address=IF(?, ?, address),
+                   is_active=COALESCE(?, is_active),
+                   preferred_locale=COALESCE(?, preferred_locale)
+               WHERE id=? AND deleted_at IS NULL"#,
+            patch.name,
+            patch.phone_number.is_some(),
+            patch.phone_number.clone().flatten(),
+            patch.address.is_some(),
+            patch.address.clone().flatten(),
+            patch.is_active,
+            patch.preferred_locale.map(Locale::tag),
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_customer(&mut self, id: i64) -> Result<()> {
+        sqlx::query!(
+            "UPDATE customers SET deleted_at=NOW() WHERE id=? AND deleted_at IS NULL",
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn restore_customer(&mut self, id: i64) -> Result<bool> {
+        let query_result = sqlx::query!(
+            "UPDATE customers SET deleted_at=NULL WHERE id=? AND deleted_at IS NOT NULL",
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn purge_customers(&mut self, retention_days: i64) -> Result<u64> {
+        let query_result = sqlx::query!(
+            "DELETE FROM customers WHERE deleted_at < NOW() - INTERVAL ? DAY",
+            retention_days
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected())
+    }
+
+    async fn get_customer(&mut self, id: i64) -> Result<CustomerResponse> {
+        let customer = sqlx::query_as!(
+            CustomerResponse,
+            r#"SELECT id, name, email, password, phone_number, address,
+                      is_active as `is_active: _`, preferred_locale
+               FROM customers WHERE id=? AND deleted_at IS NULL"#,
+            id
+        )
+        .fetch_one(&mut *self)
+        .await?;
+
+        Ok(customer)
+    }
+
+    async fn get_customers(&mut self) -> Result<Vec<CustomerResponse>> {
+        let customers = sqlx::query_as!(
+            CustomerResponse,
+            r#"SELECT id, name, email, password, phone_number, address,
+                      is_active as `is_active: _`, preferred_locale
+               FROM customers WHERE deleted_at IS NULL"#
+        )
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(customers)
+    }
+
+    async fn find_customer_by_email(&mut self, email: &str) -> Result<Option<CustomerResponse>> {
+        let maybe_customer = sqlx::query_as!(
+            CustomerResponse,
+            r#"SELECT id, name, email, password, phone_number, address,
+                      is_active as `is_active: _`, preferred_locale
+               FROM customers WHERE email=? AND deleted_at IS NULL"#,
+            email
+        )
+        .fetch_optional(&mut *self)
+        .await?;
+
+        Ok(maybe_customer)
+    }
+
+    async fn find_customer_id_by_email(&mut self, email: &str) -> Result<Option<i64>> {
+        let maybe_id = sqlx::query_scalar!("SELECT id FROM customers WHERE email=?", email)
+            .fetch_optional(&mut *self)
+            .await?;
+
+        Ok(maybe_id)
+    }
+
+    async fn find_customer_by_login(
+        &mut self,
+        email: &str,
+        hashed_password: &str,
+    ) -> Result<Option<CustomerResponse>> {
+        let maybe_customer = sqlx::query_as!(
+            CustomerResponse,
+            r#"SELECT id, name, email, password, phone_number, address,
+                      is_active as `is_active: _`, preferred_locale
+               FROM customers
+               WHERE email=? AND password=? AND is_active=TRUE AND deleted_at IS NULL"#,
+            email,
+            hashed_password
+        )
+        .fetch_optional(&mut *self)
+        .await?;
+
+        Ok(maybe_customer)
+    }
+
+    async fn find_admin_id_by_login(
+        &mut self,
+        email: &str,
+        hashed_password: &str,
+    ) -> Result<Option<i64>> {
+        let maybe_id = sqlx::query_scalar!(
+            r#"SELECT id FROM customers
+               WHERE email=? AND password=? AND is_admin=TRUE AND is_active=TRUE
+                 AND deleted_at IS NULL"#,
+            email,
+            hashed_password
+        )
+        .fetch_optional(&mut *self)
+        .await?;
+
+        Ok(maybe_id)
+    }
+
+    async fn set_customer_password(&mut self, id: i64, hashed_password: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE customers SET password=? WHERE id=? AND deleted_at IS NULL",
+            hashed_password,
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_password_reset_token(
+        &mut self,
+        id: i64,
+        token: &str,
+        ttl_minutes: i64,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE customers
+               SET password_reset_token=?, password_reset_expires_at=NOW() + INTERVAL ? MINUTE
+               WHERE id=? AND deleted_at IS NULL"#,
+            token,
+            ttl_minutes,
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn reset_customer_password(
+        &mut self,
+        token: &str,
+        hashed_password: &str,
+    ) -> Result<bool> {
+        let query_result = sqlx::query!(
+            r#"UPDATE customers
+               SET password=?, password_reset_token=NULL, password_reset_expires_at=NULL
+               WHERE password_reset_token=? AND password_reset_expires_at > NOW()
+                 AND deleted_at IS NULL"#,
+            hashed_password,
+            token
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn set_activation_token(&mut self, id: i64, token: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE customers SET activation_token=? WHERE id=? AND deleted_at IS NULL",
+            token,
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn activate_customer(&mut self, token: &str) -> Result<bool> {
+        let query_result = sqlx::query!(
+            r#"UPDATE customers
+               SET is_active=TRUE, activation_token=NULL
+               WHERE activation_token=? AND deleted_at IS NULL"#,
+            token
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn set_pending_email(&mut self, id: i64, pending_email: &str, token: &str) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE customers
+               SET pending_email=?, email_change_token=?
+               WHERE id=? AND deleted_at IS NULL"#,
+            pending_email,
+            token,
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn confirm_email_change(&mut self, token: &str) -> Result<bool> {
+        let query_result = sqlx::query!(
+            r#"UPDATE customers
+               SET email=pending_email, pending_email=NULL, email_change_token=NULL,
+                   password_reset_token=NULL, password_reset_expires_at=NULL
+               WHERE email_change_token=? AND pending_email IS NOT NULL AND deleted_at IS NULL"#,
+            token
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn cancel_email_change(&mut self, id: i64) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE customers
+               SET pending_email=NULL, email_change_token=NULL
+               WHERE id=? AND deleted_at IS NULL"#,
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn cancel_email_change_with_token(&mut self, token: &str) -> Result<bool> {
+        let query_result = sqlx::query!(
+            r#"UPDATE customers
+               SET pending_email=NULL, email_change_token=NULL
+               WHERE email_change_token=? AND deleted_at IS NULL"#,
+            token
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn upsert_admin(&mut self, name: &str, email: &str, hashed_password: &str) -> Result<()> {
+        sqlx::query!(
+            r#"INSERT INTO customers (name, email, password, is_active, is_admin)
+               VALUES (?, ?, ?, TRUE, TRUE)
+               ON DUPLICATE KEY UPDATE
+                   name=VALUES(name), password=VALUES(password), is_active=TRUE, is_admin=TRUE,
+                   deleted_at=NULL"#,
+            name,
+            email,
+            hashed_password
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl OrderRepo for MySqlConnection {
+    async fn insert_order(&mut self, customer_id: i64) -> Result<i64> {
+        let query_result = sqlx::query!(
+            r#"INSERT INTO orders (customer_id)
+               VALUES (?)"#,
+            customer_id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.last_insert_id() as i64)
+    }
+
+    async fn set_order_customer(&mut self, id: i64, customer_id: i64) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE orders
+               SET customer_id=?
+               WHERE id=?"#,
+            customer_id,
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_order_items(&mut self, order_id: i64, items: &[OrderItemRequest]) -> Result<()> {
+        sqlx::query!("DELETE FROM order_items WHERE order_id=?", order_id)
+            .execute(&mut *self)
+            .await?;
+
+        for item in items {
+            sqlx::query!(
+                r#"INSERT INTO order_items (order_id, product_id, amount)
+                   VALUES (?, ?, ?)"#,
+                order_id,
+                item.product_id,
+                item.amount
+            )
+            .execute(&mut *self)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_order(&mut self, id: i64) -> Result<()> {
+        sqlx::query!("DELETE FROM orders WHERE id=?", id)
+            .execute(&mut *self)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_order(&mut self, id: i64) -> Result<OrderResponse> {
+        let order_row = sqlx::query!(
+            r#"SELECT customer_id
+               FROM orders
+               WHERE id=?"#,
+            id
+        )
+        .fetch_one(&mut *self)
+        .await?;
+
+        Ok(OrderResponse {
+            id,
+            customer_id: order_row.customer_id,
+            items: order_items(self, id).await?,
+        })
+    }
+
+    async fn get_orders(&mut self) -> Result<Vec<OrderResponse>> {
+        let rows = sqlx::query!("SELECT id, customer_id FROM orders")
+            .fetch_all(&mut *self)
+            .await?;
+
+        let mut orders = Vec::new();
+
+        for row in rows {
+            orders.push(OrderResponse {
+                id: row.id,
+                customer_id: row.customer_id,
+                items: order_items(self, row.id).await?,
+            });
+        }
+
+        Ok(orders)
+    }
+
+    async fn get_orders_by_customer(&mut self, customer_id: i64) -> Result<Vec<OrderResponse>> {
+        let rows = sqlx::query!(
+            r#"SELECT id
+               FROM orders
+               WHERE customer_id=?"#,
+            customer_id
+        )
+        .fetch_all(&mut *self)
+        .await?;
+
+        let mut orders = Vec::new();
+
+        for row in rows {
+            orders.push(OrderResponse {
+                id: row.id,
+                customer_id,
+                items: order_items(self, row.id).await?,
+            });
+        }
+
+        Ok(orders)
+    }
+}
+
+async fn order_items(
+    connection: &mut MySqlConnection,
+    order_id: i64,
+) -> Result<Vec<OrderItemResponse>> {
+    let items = sqlx::query_as!(
+        OrderItemResponse,
+        r#"SELECT order_items.id, order_items.product_id, products.name AS product_name,
+                  products.price AS product_price, order_items.amount
+           FROM order_items
+           JOIN products ON products.id=order_items.product_id
+           WHERE order_items.order_id=?"#,
+        order_id
+    )
+    .fetch_all(connection)
+    .await?;
+
+    Ok(items)
+}
+
+impl OutboxRepo for MySqlConnection {
+    async fn enqueue_email(&mut self, email: &Email) -> Result<()> {
+        sqlx::query!(
+            r#"INSERT INTO email_outbox (recipient, subject, body, text_body)
+               VALUES (?, ?, ?, ?)"#,
+            email.to,
+            email.subject,
+            email.html_body,
+            email.text_body
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn lock_next_due_email(&mut self) -> Result<Option<QueuedEmail>> {
+        let maybe_email = sqlx::query_as!(
+            QueuedEmail,
+            r#"SELECT id, recipient, subject, body, text_body, attempts
+               FROM email_outbox
+               WHERE status='pending' AND next_attempt_at <= NOW()
+               ORDER BY next_attempt_at
+               LIMIT 1
+               FOR UPDATE SKIP LOCKED"#
+        )
+        .fetch_optional(&mut *self)
+        .await?;
+
+        Ok(maybe_email)
+    }
+
+    async fn mark_email_sent(&mut self, id: i64, attempts: i32) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE email_outbox
+               SET status='sent', attempts=?, last_error=NULL, sent_at=NOW()
+               WHERE id=?"#,
+            attempts,
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn mark_email_failed(
+        &mut self,
+        id: i64,
+        status: &str,
+        attempts: i32,
+        error_message: &str,
+        retry_in_secs: i64,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE email_outbox
+               SET status=?, attempts=?, last_error=?,
+                   next_attempt_at=NOW() + INTERVAL ? SECOND
+               WHERE id=?"#,
+            status,
+            attempts,
+            error_message,
+            retry_in_secs,
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_outbox_emails(
+        &mut self,
+        status: Option<&str>,
+    ) -> Result<Vec<EmailOutboxResponse>> {
+        let emails = sqlx::query_as!(
+            EmailOutboxResponse,
+            r#"SELECT id, recipient, subject, status, attempts, last_error,
+                      next_attempt_at, created_at, sent_at
+               FROM email_outbox
+               WHERE ? IS NULL OR status=?
+               ORDER BY id DESC"#,
+            status,
+            status
+        )
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(emails)
+    }
+
+    async fn retry_outbox_email(&mut self, id: i64) -> Result<bool> {
+        let query_result = sqlx::query!(
+            r#"UPDATE email_outbox
+               SET status='pending', attempts=0, next_attempt_at=NOW()
+               WHERE id=? AND status<>'sent'"#,
+            id
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+}
+
+impl RateLimitRepo for MySqlConnection {
+    async fn lock_rate_limit_bucket(
+        &mut self,
+        bucket_key: &str,
+        capacity: f64,
+    ) -> Result<(f64, f64)> {
+        sqlx::query!(
+            "INSERT IGNORE INTO rate_limit_buckets (bucket_key, tokens) VALUES (?, ?)",
+            bucket_key,
+            capacity
+        )
+        .execute(&mut *self)
+        .await?;
+
+        let bucket = sqlx::query!(
+            r#"SELECT tokens,
+                      TIMESTAMPDIFF(MICROSECOND, updated_at, NOW(3)) AS "elapsed_us!: i64"
+               FROM rate_limit_buckets
+               WHERE bucket_key=?
+               FOR UPDATE"#,
+            bucket_key
+        )
+        .fetch_one(&mut *self)
+        .await?;
+
+        Ok((bucket.tokens, bucket.elapsed_us as f64 / 1e6))
+    }
+
+    async fn update_rate_limit_bucket(&mut self, bucket_key: &str, tokens: f64) -> Result<()> {
+        sqlx::query!(
+            "UPDATE rate_limit_buckets SET tokens=?, updated_at=NOW(3) WHERE bucket_key=?",
+            tokens,
+            bucket_key
+        )
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn purge_rate_limit_buckets(&mut self) -> Result<()> {
+        sqlx::query!("DELETE FROM rate_limit_buckets WHERE updated_at < NOW() - INTERVAL 1 DAY")
+            .execute(&mut *self)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,830 @@
+use sqlx::SqliteConnection;
+
+use super::{CustomerRepo, OrderRepo, OutboxRepo, ProductRepo, RateLimitRepo};
+use crate::{
+    Result,
+    customer::{CustomerPatchRequest, CustomerRequest, CustomerResponse},
+    mailer::Email,
+    order::{OrderItemRequest, OrderItemResponse, OrderResponse},
+    outbox::{EmailOutboxResponse, QueuedEmail},
+    product::{ProductPatchRequest, ProductRequest, ProductResponse, ProductStatus},
+    templates::Locale,
+};
+
+const PRODUCT_COLUMNS: &str =
+    "id, name, description, price, is_featured, status, publish_at, unpublish_at";
+const CUSTOMER_COLUMNS: &str =
+    "id, name, email, password, phone_number, address, is_active, preferred_locale";
+/// Same format as `CURRENT_TIMESTAMP`, so stored timestamps compare as text
+const NOW: &str = "datetime('now')";
+/// With milliseconds, for the rate limit buckets
+const NOW_MS: &str = "strftime('%Y-%m-%d %H:%M:%f', 'now')";
+
+impl ProductRepo for SqliteConnection {
+    async fn insert_product(&mut self, product: &ProductRequest) -> Result<i64> {
+        let query_result = sqlx::query(
+            r#"INSERT INTO products
+               (name, description, price, is_featured, status, publish_at, unpublish_at)
+               VALUES (?, ?, ?, ?, ?, ?, ?)"#,
+        )
+        .bind(&product.name)
+        .bind(&product.description)
+        .bind(product.price)
+        .bind(product.is_featured)
+        .bind(product.status.unwrap_or_default().name())
+        .bind(product.publish_at)
+        .bind(product.unpublish_at)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.last_insert_rowid())
+    }
+
+    async fn update_product(&mut self, id: i64, product: &ProductRequest) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE products
+               SET name=?, description=?, price=?, is_featured=?,
+                   status=COALESCE(?, status), publish_at=?, unpublish_at=?
+               WHERE id=? AND deleted_at IS NULL"#,
+        )
+        .bind(&product.name)
+        .bind(&product.description)
+        .bind(product.price)
+        .bind(product.is_featured)
+        .bind(product.status.map(ProductStatus::name))
+        .bind(product.publish_at)
+        .bind(product.unpublish_at)
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn patch_product(&mut self, id: i64, patch: &ProductPatchRequest) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE products
+               SET name=COALESCE(?, name),
+                   description=CASE WHEN ? THEN ? ELSE description END,
+                   price=COALESCE(?, price),
+                   is_featured=COALESCE(?, is_featured),
+                   status=COALESCE(?, status),
+                   publish_at=CASE WHEN ? THEN ? ELSE publish_at END,
+                   unpublish_at=CASE WHEN ? THEN ? ELSE unpublish_at END
+               WHERE id=? AND deleted_at IS NULL"#,
+        )
+        .bind(&patch.name)
+        .bind(patch.description.is_some())
+        .bind(patch.description.clone().flatten())
+        .bind(patch.price)
+        .bind(patch.is_featured)
+        .bind(patch.status.map(ProductStatus::name))
+        .bind(patch.publish_at.is_some())
+        .bind(patch.publish_at.flatten())
+        .bind(patch.unpublish_at.is_some())
+        .bind(patch.unpublish_at.flatten())
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_product(&mut self, id: i64) -> Result<()> {
+        sqlx::query(&format!(
+            "UPDATE products SET deleted_at={NOW} WHERE id=? AND deleted_at IS NULL"
+        ))
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn restore_product(&mut self, id: i64) -> Result<bool> {
+        let query_result = sqlx::query(
+            "UPDATE products SET deleted_at=NULL WHERE id=? AND deleted_at IS NOT NULL",
+        )
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn purge_products(&mut self, retention_days: i64) -> Result<u64> {
+        let query_result = sqlx::query(
+            r#"DELETE FROM products
+               WHERE deleted_at < datetime('now', '-' || ? || ' days')
+                 AND NOT EXISTS (SELECT 1 FROM order_items WHERE product_id=products.id)"#,
+        )
+        .bind(retention_days)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected())
+    }
+
+    async fn get_product(&mut self, id: i64) -> Result<ProductResponse> {
+        let product = sqlx::query_as(&format!(
+            "SELECT {PRODUCT_COLUMNS} FROM products WHERE id=? AND deleted_at IS NULL"
+        ))
+        .bind(id)
+        .fetch_one(&mut *self)
+        .await?;
+
+        Ok(product)
+    }
+
+    async fn get_published_product(&mut self, id: i64) -> Result<ProductResponse> {
+        let product = sqlx::query_as(&format!(
+            r#"SELECT {PRODUCT_COLUMNS} FROM products
+               WHERE id=? AND status='published' AND deleted_at IS NULL"#
+        ))
+        .bind(id)
+        .fetch_one(&mut *self)
+        .await?;
+
+        Ok(product)
+    }
+
+    async fn get_products(&mut self) -> Result<Vec<ProductResponse>> {
+        let products = sqlx::query_as(&format!(
+            r#"SELECT {PRODUCT_COLUMNS} FROM products
+               WHERE status='published' AND deleted_at IS NULL"#
+        ))
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(products)
+    }
+
+    async fn get_featured_products(&mut self) -> Result<Vec<ProductResponse>> {
+        let products = sqlx::query_as(&format!(
+            r#"SELECT {PRODUCT_COLUMNS} FROM products
+               WHERE is_featured=TRUE AND status='published' AND deleted_at IS NULL"#
+        ))
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(products)
+    }
+
+    async fn get_products_by_status(
+        &mut self,
+        status: Option<ProductStatus>,
+    ) -> Result<Vec<ProductResponse>> {
+        let products = sqlx::query_as(&format!(
+            r#"SELECT {PRODUCT_COLUMNS} FROM products
+               WHERE (?1 IS NULL OR status=?1) AND deleted_at IS NULL
+               ORDER BY id"#
+        ))
+        .bind(status.map(ProductStatus::name))
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(products)
+    }
+
+    async fn publish_due_products(&mut self) -> Result<u64> {
+        let query_result = sqlx::query(&format!(
+            r#"UPDATE products SET status='published', publish_at=NULL
+               WHERE status='draft' AND publish_at <= {NOW} AND deleted_at IS NULL"#
+        ))
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected())
+    }
+
+    async fn unpublish_due_products(&mut self) -> Result<u64> {
+        let query_result = sqlx::query(&format!(
+            r#"UPDATE products SET status='archived', unpublish_at=NULL
+               WHERE status='published' AND unpublish_at <= {NOW}
+                 AND deleted_at IS NULL"#
+        ))
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected())
+    }
+
+    async fn search_products(&mut self, term: &str) -> Result<Vec<ProductResponse>> {
+        let products = sqlx::query_as(&format!(
+            r#"SELECT {PRODUCT_COLUMNS} FROM products
+               WHERE name LIKE ? AND status='published' AND deleted_at IS NULL"#
+        ))
+        .bind(term)
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(products)
+    }
+
+    async fn find_product_id_by_name(&mut self, name: &str) -> Result<Option<i64>> {
+        let maybe_id = sqlx::query_scalar("SELECT id FROM products WHERE name=? LIMIT 1")
+            .bind(name)
+            .fetch_optional(&mut *self)
+            .await?;
+
+        Ok(maybe_id)
+    }
+}
+
+impl CustomerRepo for SqliteConnection {
+    async fn insert_customer(
+        &mut self,
+        customer: &CustomerRequest,
+        hashed_password: &str,
+    ) -> Result<i64> {
+        let query_result = sqlx::query(
+            r#"INSERT INTO customers
+               (name, email, password, phone_number, address, is_active, preferred_locale)
+               VALUES (?, ?, ?, ?, ?, ?, ?)"#,
+        )
+        .bind(&customer.name)
+        .bind(&customer.email)
+        .bind(hashed_password)
+        .bind(&customer.phone_number)
+        .bind(&customer.address)
+        .bind(customer.is_active.unwrap_or(false))
+        .bind(customer.preferred_locale.unwrap_or_default().tag())
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.last_insert_rowid())
+    }
+
+    async fn update_customer(
+        &mut self,
+        id: i64,
+        customer: &CustomerRequest,
+        hashed_password: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE customers
+               SET name=?, password=?, phone_number=?, address=?,
+                   is_active=COALESCE(?, is_active),
+                   preferred_locale=COALESCE(?, preferred_locale)
+               WHERE id=? AND deleted_at IS NULL"#,
+        )
+        .bind(&customer.name)
+        .bind(hashed_password)
+        .bind(&customer.phone_number)
+        .bind(&customer.address)
+        .bind(customer.is_active)
+        .bind(customer.preferred_locale.map(Locale::tag))
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn patch_customer(&mut self, id: i64, patch: &CustomerPatchRequest) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE customers
+               SET name=COALESCE(?, name),
+                   phone_number=CASE WHEN ? THEN ? ELSE phone_number END,
+                   address=CASE WHEN ? THEN ? ELSE address END,
+                   is_active=COALESCE(?, is_active),
+                   preferred_locale=COALESCE(?, preferred_locale)
+               WHERE id=? AND deleted_at IS NULL"#,
+        )
+        .bind(&patch.name)
+        .bind(patch.phone_number.is_some())
+        .bind(patch.phone_number.clone().flatten())
+        .bind(patch.address.is_some())
+        .bind(patch.address.clone().flatten())
+        .bind(patch.is_active)
+        .bind(patch.preferred_locale.map(Locale::tag))
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_customer(&mut self, id: i64) -> Result<()> {
+        sqlx::query(&format!(
+            "UPDATE customers SET deleted_at={NOW} WHERE id=? AND deleted_at IS NULL"
+        ))
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn restore_customer(&mut self, id: i64) -> Result<bool> {
+        let query_result = sqlx::query(
+            "UPDATE customers SET deleted_at=NULL WHERE id=? AND deleted_at IS NOT NULL",
+        )
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn purge_customers(&mut self, retention_days: i64) -> Result<u64> {
+        let query_result = sqlx::query(
+            r#"DELETE FROM customers
+               WHERE deleted_at < datetime('now', '-' || ? || ' days')"#,
+        )
+        .bind(retention_days)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected())
+    }
+
+    async fn get_customer(&mut self, id: i64) -> Result<CustomerResponse> {
+        let customer = sqlx::query_as(&format!(
+            "SELECT {CUSTOMER_COLUMNS} FROM customers WHERE id=? AND deleted_at IS NULL"
+        ))
+        .bind(id)
+        .fetch_one(&mut *self)
+        .await?;
+
+        Ok(customer)
+    }
+
+    async fn get_customers(&mut self) -> Result<Vec<CustomerResponse>> {
+        let customers = sqlx::query_as(&format!(
+            "SELECT {CUSTOMER_COLUMNS} FROM customers WHERE deleted_at IS NULL"
+        ))
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(customers)
+    }
+
+    async fn find_customer_by_email(&mut self, email: &str) -> Result<Option<CustomerResponse>> {
+        let maybe_customer = sqlx::query_as(&format!(
+            "SELECT {CUSTOMER_COLUMNS} FROM customers WHERE email=? AND deleted_at IS NULL"
+        ))
+        .bind(email)
+        .fetch_optional(&mut *self)
+        .await?;
+
+        Ok(maybe_customer)
+    }
+
+    async fn find_customer_id_by_email(&mut self, email: &str) -> Result<Option<i64>> {
+        let maybe_id = sqlx::query_scalar("SELECT id FROM customers WHERE email=?")
+            .bind(email)
+            .fetch_optional(&mut *self)
+            .await?;
+
+        Ok(maybe_id)
+    }
+
+    async fn find_customer_by_login(
+        &mut self,
+        email: &str,
+        hashed_password: &str,
+    ) -> Result<Option<CustomerResponse>> {
+        let maybe_customer = sqlx::query_as(&format!(
+            r#"SELECT {CUSTOMER_COLUMNS}
+               FROM customers
+               WHERE email=? AND password=? AND is_active=TRUE AND deleted_at IS NULL"#
+        ))
+        .bind(email)
+        .bind(hashed_password)
+        .fetch_optional(&mut *self)
+        .await?;
+
+        Ok(maybe_customer)
+    }
+
+    async fn find_admin_id_by_login(
+        &mut self,
+        email: &str,
+        hashed_password: &str,
+    ) -> Result<Option<i64>> {
+        let maybe_id = sqlx::query_scalar(
+            r#"SELECT id FROM customers
+               WHERE email=? AND password=? AND is_admin=TRUE AND is_active=TRUE
+                 AND deleted_at IS NULL"#,
+        )
+        .bind(email)
+        .bind(hashed_password)
+        .fetch_optional(&mut *self)
+        .await?;
+
+        Ok(maybe_id)
+    }
+
+    async fn set_customer_password(&mut self, id: i64, hashed_password: &str) -> Result<()> {
+        sqlx::query("UPDATE customers SET password=? WHERE id=? AND deleted_at IS NULL")
+            .bind(hashed_password)
+            .bind(id)
+            .execute(&mut *self)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_password_reset_token(
+        &mut self,
+        id: i64,
+        token: &str,
+        ttl_minutes: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE customers
+               SET password_reset_token=?,
+                   password_reset_expires_at=datetime('now', ? || ' minutes')
+               WHERE id=? AND deleted_at IS NULL"#,
+        )
+        .bind(token)
+        .bind(ttl_minutes)
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn reset_customer_password(
+        &mut self,
+        token: &str,
+        hashed_password: &str,
+    ) -> Result<bool> {
+        let query_result = sqlx::query(&format!(
+            r#"UPDATE customers
+               SET password=?, password_reset_token=NULL, password_reset_expires_at=NULL
+               WHERE password_reset_token=? AND password_reset_expires_at > {NOW}
+                 AND deleted_at IS NULL"#
+        ))
+        .bind(hashed_password)
+        .bind(token)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn set_activation_token(&mut self, id: i64, token: &str) -> Result<()> {
+        sqlx::query("UPDATE customers SET activation_token=? WHERE id=? AND deleted_at IS NULL")
+            .bind(token)
+            .bind(id)
+            .execute(&mut *self)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn activate_customer(&mut self, token: &str) -> Result<bool> {
+        let query_result = sqlx::query(
+            r#"UPDATE customers
+               SET is_active=TRUE, activation_token=NULL
+               WHERE activation_token=? AND deleted_at IS NULL"#,
+        )
+        .bind(token)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn set_pending_email(&mut self, id: i64, pending_email: &str, token: &str) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE customers
+               SET pending_email=?, email_change_token=?
+               WHERE id=? AND deleted_at IS NULL"#,
+        )
+        .bind(pending_email)
+        .bind(token)
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn confirm_email_change(&mut self, token: &str) -> Result<bool> {
+        let query_result = sqlx::query(
+            r#"UPDATE customers
+               SET email=pending_email, pending_email=NULL, email_change_token=NULL,
+                   password_reset_token=NULL, password_reset_expires_at=NULL
+               WHERE email_change_token=? AND pending_email IS NOT NULL AND deleted_at IS NULL"#,
+        )
+        .bind(token)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn cancel_email_change(&mut self, id: i64) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE customers
+               SET pending_email=NULL, email_change_token=NULL
+               WHERE id=? AND deleted_at IS NULL"#,
+        )
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn cancel_email_change_with_token(&mut self, token: &str) -> Result<bool> {
+        let query_result = sqlx::query(
+            r#"UPDATE customers
+               SET pending_email=NULL, email_change_token=NULL
+               WHERE email_change_token=? AND deleted_at IS NULL"#,
+        )
+        .bind(token)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+
+    async fn upsert_admin(&mut self, name: &str, email: &str, hashed_password: &str) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO customers (name, email, password, is_active, is_admin)
+               VALUES (?, ?, ?, TRUE, TRUE)
+               ON CONFLICT (email) DO UPDATE
+               SET name=excluded.name, password=excluded.password, is_active=TRUE, is_admin=TRUE,
+                   deleted_at=NULL"#,
+        )
+        .bind(name)
+        .bind(email)
+        .bind(hashed_password)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl OrderRepo for SqliteConnection {
+    async fn insert_order(&mut self, customer_id: i64) -> Result<i64> {
+        let query_result = sqlx::query("INSERT INTO orders (customer_id) VALUES (?)")
+            .bind(customer_id)
+            .execute(&mut *self)
+            .await?;
+
+        Ok(query_result.last_insert_rowid())
+    }
+
+    async fn set_order_customer(&mut self, id: i64, customer_id: i64) -> Result<()> {
+        sqlx::query("UPDATE orders SET customer_id=? WHERE id=?")
+            .bind(customer_id)
+            .bind(id)
+            .execute(&mut *self)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_order_items(&mut self, order_id: i64, items: &[OrderItemRequest]) -> Result<()> {
+        sqlx::query("DELETE FROM order_items WHERE order_id=?")
+            .bind(order_id)
+            .execute(&mut *self)
+            .await?;
+
+        for item in items {
+            sqlx::query(
+                r#"INSERT INTO order_items (order_id, product_id, amount)
+                   VALUES (?, ?, ?)"#,
+            )
+            .bind(order_id)
+            .bind(item.product_id)
+            .bind(item.amount)
+            .execute(&mut *self)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_order(&mut self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM orders WHERE id=?")
+            .bind(id)
+            .execute(&mut *self)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_order(&mut self, id: i64) -> Result<OrderResponse> {
+        let customer_id = sqlx::query_scalar("SELECT customer_id FROM orders WHERE id=?")
+            .bind(id)
+            .fetch_one(&mut *self)
+            .await?;
+
+        Ok(OrderResponse {
+            id,
+            customer_id,
+            items: order_items(self, id).await?,
+        })
+    }
+
+    async fn get_orders(&mut self) -> Result<Vec<OrderResponse>> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as("SELECT id, customer_id FROM orders")
+            .fetch_all(&mut *self)
+            .await?;
+
+        let mut orders = Vec::new();
+
+        for (id, customer_id) in rows {
+            orders.push(OrderResponse {
+                id,
+                customer_id,
+                items: order_items(self, id).await?,
+            });
+        }
+
+        Ok(orders)
+    }
+
+    async fn get_orders_by_customer(&mut self, customer_id: i64) -> Result<Vec<OrderResponse>> {
+        let ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM orders WHERE customer_id=?")
+            .bind(customer_id)
+            .fetch_all(&mut *self)
+            .await?;
+
+        let mut orders = Vec::new();
+
+        for id in ids {
+            orders.push(OrderResponse {
+                id,
+                customer_id,
+                items: order_items(self, id).await?,
+            });
+        }
+
+        Ok(orders)
+    }
+}
+
+async fn order_items(
+    connection: &mut SqliteConnection,
+    order_id: i64,
+) -> Result<Vec<OrderItemResponse>> {
+    let items = sqlx::query_as(
+        r#"SELECT order_items.id, order_items.product_id, products.name AS product_name,
+                  products.price AS product_price, order_items.amount
+           FROM order_items
+           JOIN products ON products.id=order_items.product_id
+           WHERE order_items.order_id=?"#,
+    )
+    .bind(order_id)
+    .fetch_all(connection)
+    .await?;
+
+    Ok(items)
+}
+
+impl OutboxRepo for SqliteConnection {
+    async fn enqueue_email(&mut self, email: &Email) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO email_outbox (recipient, subject, body, text_body)
+               VALUES (?, ?, ?, ?)"#,
+        )
+        .bind(&email.to)
+        .bind(&email.subject)
+        .bind(&email.html_body)
+        .bind(&email.text_body)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    /// There are no row locks, a single instance is expected to run the worker.
+    async fn lock_next_due_email(&mut self) -> Result<Option<QueuedEmail>> {
+        let maybe_email = sqlx::query_as(&format!(
+            r#"SELECT id, recipient, subject, body, text_body, attempts
+               FROM email_outbox
+               WHERE status='pending' AND next_attempt_at <= {NOW}
+               ORDER BY next_attempt_at
+               LIMIT 1"#
+        ))
+        .fetch_optional(&mut *self)
+        .await?;
+
+        Ok(maybe_email)
+    }
+
+    async fn mark_email_sent(&mut self, id: i64, attempts: i32) -> Result<()> {
+        sqlx::query(&format!(
+            r#"UPDATE email_outbox
+               SET status='sent', attempts=?, last_error=NULL, sent_at={NOW}
+               WHERE id=?"#
+        ))
+        .bind(attempts)
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn mark_email_failed(
+        &mut self,
+        id: i64,
+        status: &str,
+        attempts: i32,
+        error_message: &str,
+        retry_in_secs: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE email_outbox
+               SET status=?, attempts=?, last_error=?,
+                   next_attempt_at=datetime('now', ? || ' seconds')
+               WHERE id=?"#,
+        )
+        .bind(status)
+        .bind(attempts)
+        .bind(error_message)
+        .bind(retry_in_secs)
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_outbox_emails(
+        &mut self,
+        status: Option<&str>,
+    ) -> Result<Vec<EmailOutboxResponse>> {
+        let emails = sqlx::query_as(
+            r#"SELECT id, recipient, subject, status, attempts, last_error,
+                      next_attempt_at, created_at, sent_at
+               FROM email_outbox
+               WHERE ?1 IS NULL OR status=?1
+               ORDER BY id DESC"#,
+        )
+        .bind(status)
+        .fetch_all(&mut *self)
+        .await?;
+
+        Ok(emails)
+    }
+
+    async fn retry_outbox_email(&mut self, id: i64) -> Result<bool> {
+        let query_result = sqlx::query(&format!(
+            r#"UPDATE email_outbox
+               SET status='pending', attempts=0, next_attempt_at={NOW}
+               WHERE id=? AND status<>'sent'"#
+        ))
+        .bind(id)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(query_result.rows_affected() > 0)
+    }
+}
+
+impl RateLimitRepo for SqliteConnection {
+    /// The insert takes the database write lock, which stands in for the row lock.
+    async fn lock_rate_limit_bucket(
+        &mut self,
+        bucket_key: &str,
+        capacity: f64,
+    ) -> Result<(f64, f64)> {
+        sqlx::query("INSERT OR IGNORE INTO rate_limit_buckets (bucket_key, tokens) VALUES (?, ?)")
+            .bind(bucket_key)
+            .bind(capacity)
+            .execute(&mut *self)
+            .await?;
+
+        let bucket = sqlx::query_as(
+            r#"SELECT tokens, (julianday('now') - julianday(updated_at)) * 86400.0
+               FROM rate_limit_buckets
+               WHERE bucket_key=?"#,
+        )
+        .bind(bucket_key)
+        .fetch_one(&mut *self)
+        .await?;
+
+        Ok(bucket)
+    }
+
+    async fn update_rate_limit_bucket(&mut self, bucket_key: &str, tokens: f64) -> Result<()> {
+        sqlx::query(&format!(
+            "UPDATE rate_limit_buckets SET tokens=?, updated_at={NOW_MS} WHERE bucket_key=?"
+        ))
+        .bind(tokens)
+        .bind(bucket_key)
+        .execute(&mut *self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn purge_rate_limit_buckets(&mut self) -> Result<()> {
+        sqlx::query("DELETE FROM rate_limit_buckets WHERE updated_at < datetime('now', '-1 day')")
+            .execute(&mut *self)
+            .await?;
+
+        Ok(())
+    }
+}
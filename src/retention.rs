@@ -0,0 +1,62 @@
+use actix_web::{
+    HttpResponse,
+    web::{Data, ServiceConfig},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    AppData, Result,
+    db::with_pool,
+    repository::{CustomerRepo, ProductRepo},
+};
+
+#[derive(Serialize, ToSchema)]
+pub struct PurgeResponse {
+    pub customers: u64,
+    pub products: u64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/purge",
+    tag = "admin",
+    security(("admin_auth" = [])),
+    responses(
+        (status = 200, description = "How many deleted rows past `deleted_retention_days` were removed", body = PurgeResponse),
+        (status = 401, description = "Admin credentials missing or wrong", body = String, content_type = "text/plain"),
+        (status = 500, description = "Unexpected error", body = String, content_type = "text/plain"),
+    )
+)]
+#[actix_web::post("/purge")]
+#[tracing::instrument(skip_all)]
+pub async fn purge_deleted(data: Data<AppData>) -> Result<HttpResponse> {
+    let retention_days = i64::from(data.config.deleted_retention_days);
+
+    let purged = with_pool!(&data.db_pool, |pool| {
+        let mut transaction = pool.begin().await?;
+
+        // Customers first, their orders go with them and may free products to purge
+        let customers = transaction.purge_customers(retention_days).await?;
+        let products = transaction.purge_products(retention_days).await?;
+
+        transaction.commit().await?;
+
+        PurgeResponse {
+            customers,
+            products,
+        }
+    });
+
+    tracing::info!(
+        customers = purged.customers,
+        products = purged.products,
+        "purged deleted rows"
+    );
+
+    Ok(HttpResponse::Ok().json(purged))
+}
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(purge_deleted);
+}
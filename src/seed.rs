@@ -0,0 +1,285 @@
+use clap::ValueEnum;
+
+use crate::{
+    Result,
+    customer::CustomerRequest,
+    db::{DbPool, with_pool},
+    order::OrderItemRequest,
+    product::{ProductRequest, ProductStatus},
+    repository::{CustomerRepo, OrderRepo, ProductRepo},
+};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SeedProfile {
+    /// Sample catalog, customers and orders to show the shop around
+    Demo,
+    /// Small fixed data set for automated tests
+    Test,
+}
+
+struct SeedProduct {
+    name: &'static str,
+    description: &'static str,
+    price: i64,
+    is_featured: bool,
+}
+
+struct SeedCustomer {
+    name: &'static str,
+    email: &'static str,
+    password: &'static str,
+    phone_number: &'static str,
+    address: &'static str,
+    is_active: bool,
+    /// Each order is a list of (product name, amount)
+    orders: &'static [&'static [(&'static str, i64)]],
+}
+
+const DEMO_PRODUCTS: &[SeedProduct] = &[
+    SeedProduct {
+        name: "Baú Aberto",
+        description: "Baú aberto",
+        price: 10,
+        is_featured: true,
+    },
+    SeedProduct {
+        name: "Cabeça de Dragão",
+        description: "Cabeça de dragão",
+        price: 5,
+        is_featured: true,
+    },
+    SeedProduct {
+        name: "Poção de Vida",
+        description: "Poção de vida",
+        price: 3,
+        is_featured: true,
+    },
+    SeedProduct {
+        name: "Escudo",
+        description: "Escudo",
+        price: 2,
+        is_featured: false,
+    },
+    SeedProduct {
+        name: "Elmo",
+        description: "Elmo",
+        price: 10,
+        is_featured: false,
+    },
+    SeedProduct {
+        name: "Chave",
+        description: "Chave",
+        price: 9,
+        is_featured: false,
+    },
+    SeedProduct {
+        name: "Bússola",
+        description: "Bússola",
+        price: 1,
+        is_featured: false,
+    },
+    SeedProduct {
+        name: "Duas Moedas",
+        description: "Duas moedas",
+        price: 100,
+        is_featured: false,
+    },
+];
+
+const DEMO_CUSTOMERS: &[SeedCustomer] = &[
+    SeedCustomer {
+        name: "Vinicius",
+        email: "vinicius@example.com",
+        password: "senha_vinicius",
+        phone_number: "11999990001",
+        address: "R. das Laranjeiras, 10, São Paulo - SP",
+        is_active: true,
+        orders: &[
+            &[("Poção de Vida", 2), ("Duas Moedas", 5)],
+            &[("Cabeça de Dragão", 1), ("Baú Aberto", 3)],
+        ],
+    },
+    SeedCustomer {
+        name: "Maria Santos",
+        email: "maria.santos@example.com",
+        password: "senha_maria",
+        phone_number: "21988880002",
+        address: "Av. Rio Branco, 123, Rio de Janeiro - RJ",
+        is_active: true,
+        orders: &[
+            &[("Elmo", 1), ("Escudo", 2)],
+            &[("Poção de Vida", 4), ("Chave", 2)],
+        ],
+    },
+    SeedCustomer {
+        name: "Pedro Almeida",
+        email: "pedro.almeida@example.com",
+        password: "senha_pedro",
+        phone_number: "31977770003",
+        address: "R. das Oliveiras, 45, Belo Horizonte - MG",
+        is_active: false,
+        orders: &[&[("Baú Aberto", 1), ("Poção de Vida", 1)]],
+    },
+    SeedCustomer {
+        name: "Ana Costa",
+        email: "ana.costa@example.com",
+        password: "senha_ana",
+        phone_number: "11977776666",
+        address: "R. das Flores, 7, São Paulo - SP",
+        is_active: true,
+        orders: &[&[("Chave", 2)]],
+    },
+    SeedCustomer {
+        name: "Empresa ABC",
+        email: "vendas@empresaabc.example.com",
+        password: "senha_empresa",
+        phone_number: "1133334444",
+        address: "Av. Paulista, 1000, São Paulo - SP",
+        is_active: true,
+        orders: &[&[("Bússola", 10), ("Elmo", 2)]],
+    },
+    SeedCustomer {
+        name: "Lucas Pereira",
+        email: "lucas.pereira@example.com",
+        password: "senha_lucas",
+        phone_number: "51999990004",
+        address: "R. das Acácias, 200, Porto Alegre - RS",
+        is_active: true,
+        orders: &[&[("Escudo", 1), ("Duas Moedas", 20), ("Cabeça de Dragão", 1)]],
+    },
+];
+
+const TEST_PRODUCTS: &[SeedProduct] = &[
+    SeedProduct {
+        name: "Test Featured Product",
+        description: "Featured product for tests",
+        price: 10,
+        is_featured: true,
+    },
+    SeedProduct {
+        name: "Test Product",
+        description: "Regular product for tests",
+        price: 3,
+        is_featured: false,
+    },
+];
+
+const TEST_CUSTOMERS: &[SeedCustomer] = &[
+    SeedCustomer {
+        name: "Active Customer",
+        email: "active@test.example.com",
+        password: "active_password",
+        phone_number: "11900000001",
+        address: "Test street, 1",
+        is_active: true,
+        orders: &[&[("Test Featured Product", 1), ("Test Product", 2)]],
+    },
+    SeedCustomer {
+        name: "Inactive Customer",
+        email: "inactive@test.example.com",
+        password: "inactive_password",
+        phone_number: "11900000002",
+        address: "Test street, 2",
+        is_active: false,
+        orders: &[],
+    },
+];
+
+/// Inserts the data of `profile` in a single transaction. Products are matched by name and
+/// customers by email, and whatever already exists is left alone, so running it again is
+/// harmless. Orders are only created along with their (new) customer.
+pub async fn run(db_pool: &DbPool, profile: SeedProfile) -> Result<()> {
+    let (products, customers) = match profile {
+        SeedProfile::Demo => (DEMO_PRODUCTS, DEMO_CUSTOMERS),
+        SeedProfile::Test => (TEST_PRODUCTS, TEST_CUSTOMERS),
+    };
+
+    with_pool!(db_pool, |pool| {
+        let mut transaction = pool.begin().await?;
+
+        for product in products {
+            seed_product(&mut *transaction, product).await?;
+        }
+
+        for customer in customers {
+            seed_customer(&mut *transaction, customer).await?;
+        }
+
+        transaction.commit().await?;
+    });
+
+    Ok(())
+}
+
+async fn seed_product(connection: &mut impl ProductRepo, product: &SeedProduct) -> Result<()> {
+    if connection
+        .find_product_id_by_name(product.name)
+        .await?
+        .is_some()
+    {
+        return Ok(());
+    }
+
+    connection
+        .insert_product(&ProductRequest {
+            name: product.name.to_owned(),
+            description: Some(product.description.to_owned()),
+            price: product.price,
+            is_featured: product.is_featured,
+            status: Some(ProductStatus::Published),
+            publish_at: None,
+            unpublish_at: None,
+        })
+        .await?;
+
+    Ok(())
+}
+
+async fn seed_customer(
+    connection: &mut (impl ProductRepo + CustomerRepo + OrderRepo),
+    customer: &SeedCustomer,
+) -> Result<()> {
+    if connection
+        .find_customer_id_by_email(customer.email)
+        .await?
+        .is_some()
+    {
+        return Ok(());
+    }
+
+    let hashed_password = crate::util::hash(customer.password);
+
+    let customer_id = connection
+        .insert_customer(
+            &CustomerRequest {
+                name: customer.name.to_owned(),
+                email: customer.email.to_owned(),
+                password: customer.password.to_owned(),
+                phone_number: Some(customer.phone_number.to_owned()),
+                address: Some(customer.address.to_owned()),
+                is_active: Some(customer.is_active),
+                preferred_locale: None,
+            },
+            &hashed_password,
+        )
+        .await?;
+
+    for items in customer.orders {
+        let order_id = connection.insert_order(customer_id).await?;
+
+        // Products that are missing are left out
+        let mut order_items = Vec::new();
+        for (product_name, amount) in items.iter() {
+            if let Some(product_id) = connection.find_product_id_by_name(product_name).await? {
+                order_items.push(OrderItemRequest {
+                    product_id,
+                    amount: *amount,
+                });
+            }
+        }
+
+        connection.set_order_items(order_id, &order_items).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use std::{sync::Arc, time::Duration};
+
+use actix_web::{App, HttpRequest, HttpServer, web};
+use anyhow::Context;
+use tokio::sync::watch;
+
+use crate::{
+    AppData, Config, build_app, cli, health, mailer, metrics, outbox, product, rate_limit,
+    templates, tls,
+};
+
+/// Runs the API until SIGTERM or SIGINT, along with the email outbox worker, the product
+/// scheduler, the mail probe, the certificate reloader and the HTTP to HTTPS redirect when
+/// configured.
+pub async fn serve(config: Arc<Config>) -> anyhow::Result<()> {
+    let db_pool = cli::connect_db(&config).await?;
+
+    let mailer = mailer::from_config(&config).context("Failed to set up the mail transport")?;
+
+    let templates =
+        Arc::new(templates::Templates::new(&config).context("Failed to load the email templates")?);
+
+    let tls_resolver = match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => Some(Arc::new(
+            tls::ReloadingCertResolver::new(cert_path, key_path)
+                .context("Failed to load the TLS certificate")?,
+        )),
+        _ => None,
+    };
+
+    let rate_limiter = Arc::new(rate_limit::RateLimiter::from_config(&config, &db_pool));
+
+    let app_data = AppData {
+        config: config.clone(),
+        db_pool,
+        mailer,
+        mail_status: Arc::new(health::MailStatus::default()),
+        metrics: Arc::new(metrics::Metrics::default()),
+        rate_limiter,
+        templates,
+    };
+
+    let db_pool = app_data.db_pool.clone();
+
+    let (shutdown_sender, shutdown_receiver) = watch::channel(false);
+    let outbox_worker = actix_web::rt::spawn(outbox::run_worker(
+        app_data.clone(),
+        shutdown_receiver.clone(),
+    ));
+    let product_scheduler = actix_web::rt::spawn(product::run_scheduler(
+        app_data.clone(),
+        shutdown_receiver.clone(),
+    ));
+    actix_web::rt::spawn(health::run_mail_probe(
+        app_data.clone(),
+        shutdown_receiver.clone(),
+    ));
+
+    let bind_host = config.server_host.clone();
+    let bind_port = config.server_port;
+
+    let mut server = HttpServer::new(move || build_app(app_data.clone()))
+        .keep_alive(Duration::from_secs(config.server_keep_alive_secs))
+        .client_request_timeout(Duration::from_millis(
+            config.server_client_request_timeout_ms,
+        ))
+        .client_disconnect_timeout(Duration::from_millis(
+            config.server_client_disconnect_timeout_ms,
+        ))
+        .shutdown_timeout(config.server_shutdown_timeout_secs);
+
+    if config.server_workers > 0 {
+        server = server.workers(config.server_workers);
+    }
+
+    let server = match &tls_resolver {
+        Some(resolver) => {
+            actix_web::rt::spawn(tls::watch_certificate(
+                resolver.clone(),
+                Duration::from_secs(config.tls_reload_interval_secs),
+                shutdown_receiver.clone(),
+            ));
+
+            server.bind_rustls_0_23(
+                (bind_host.clone(), bind_port),
+                tls::server_config(resolver.clone())?,
+            )?
+        }
+        None => server.bind((bind_host.clone(), bind_port))?,
+    };
+
+    let redirect_server = if config.http_redirect_port != 0 {
+        let redirect_server = HttpServer::new(move || {
+            App::new().default_service(web::to(move |request: HttpRequest| async move {
+                tls::redirect_to_https(&request, bind_port)
+            }))
+        })
+        // Stopped along with the main server below
+        .disable_signals()
+        .bind((bind_host.clone(), config.http_redirect_port))?
+        .run();
+
+        let redirect_server_handle = redirect_server.handle();
+        actix_web::rt::spawn(redirect_server);
+        tracing::info!(
+            port = config.http_redirect_port,
+            "redirecting HTTP to HTTPS"
+        );
+
+        Some(redirect_server_handle)
+    } else {
+        None
+    };
+
+    tracing::info!(
+        host = %bind_host,
+        port = bind_port,
+        tls = tls_resolver.is_some(),
+        "listening"
+    );
+
+    // Returns once SIGTERM or SIGINT is received and the in-flight requests are done
+    server.run().await?;
+
+    tracing::info!("shutting down");
+
+    if let Some(redirect_server_handle) = redirect_server {
+        redirect_server_handle.stop(true).await;
+    }
+
+    let _ = shutdown_sender.send(true);
+    let shutdown_timeout = Duration::from_secs(config.server_shutdown_timeout_secs);
+    if actix_web::rt::time::timeout(shutdown_timeout, outbox_worker)
+        .await
+        .is_err()
+    {
+        tracing::warn!("the email outbox worker did not stop in time");
+    }
+    if actix_web::rt::time::timeout(shutdown_timeout, product_scheduler)
+        .await
+        .is_err()
+    {
+        tracing::warn!("the product scheduler did not stop in time");
+    }
+
+    db_pool.close().await;
+
+    Ok(())
+}
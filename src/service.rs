@@ -0,0 +1,12 @@
+//! Business rules, kept apart from HTTP. Services take the repositories they need as
+//! `&mut impl ...` and leave the transaction to the caller, so a handler wraps a call between
+//! `begin` and `commit` while a test can run it on any connection:
+//!
+//! ```ignore
+//! let mut transaction = pool.begin().await?;
+//! let sign_up = service::customer::sign_up(&mut *transaction, &data, &body).await?;
+//! transaction.commit().await?;
+//! ```
+
+pub mod customer;
+pub mod order;
@@ -0,0 +1,240 @@
+use minijinja::context;
+
+use crate::{
+    AppData, Result,
+    customer::{CustomerPatchRequest, CustomerRequest, CustomerResponse},
+    error::Error,
+    mailer::Email,
+    repository::{CustomerRepo, OutboxRepo},
+    templates::Locale,
+};
+
+/// Outcome of [`sign_up`].
+pub enum SignUp {
+    Created(i64),
+    /// The email belongs to an account that is not active yet, whose activation email was queued
+    /// again.
+    ActivationResent,
+}
+
+/// Registers `customer` and queues the activation email. An unactivated account only gets its
+/// activation email again, it is never overwritten.
+pub async fn sign_up(
+    repo: &mut (impl CustomerRepo + OutboxRepo),
+    data: &AppData,
+    customer: &CustomerRequest,
+) -> Result<SignUp> {
+    if let Some(existing) = repo.find_customer_by_email(&customer.email).await? {
+        if existing.is_active {
+            return Err(Error::ConflictError(
+                "the email is already registered".to_owned(),
+            ));
+        }
+
+        queue_activation_email(
+            repo,
+            data,
+            existing.id,
+            &existing.name,
+            &customer.email,
+            Locale::from_tag(&existing.preferred_locale),
+        )
+        .await?;
+
+        return Ok(SignUp::ActivationResent);
+    }
+
+    let hashed_password = crate::util::hash(&customer.password);
+    let customer_id = repo.insert_customer(customer, &hashed_password).await?;
+
+    queue_activation_email(
+        repo,
+        data,
+        customer_id,
+        &customer.name,
+        &customer.email,
+        customer.preferred_locale.unwrap_or_default(),
+    )
+    .await?;
+
+    Ok(SignUp::Created(customer_id))
+}
+
+/// Gives the customer a new activation token, so only the latest link works, and queues it.
+async fn queue_activation_email(
+    repo: &mut (impl CustomerRepo + OutboxRepo),
+    data: &AppData,
+    id: i64,
+    name: &str,
+    email: &str,
+    locale: Locale,
+) -> Result<()> {
+    let token = crate::util::generate_token();
+    repo.set_activation_token(id, &token).await?;
+
+    let confirm_link = crate::links::activation_link(&data.config, &token);
+    let message =
+        data.templates
+            .render(email, "activation", locale, context! { name, confirm_link })?;
+
+    repo.enqueue_email(&message).await
+}
+
+/// Replaces the customer, starting an email change when the email is a new one.
+pub async fn update_customer(
+    repo: &mut (impl CustomerRepo + OutboxRepo),
+    data: &AppData,
+    id: i64,
+    customer: &CustomerRequest,
+) -> Result<()> {
+    let hashed_password = crate::util::hash(&customer.password);
+    repo.update_customer(id, customer, &hashed_password).await?;
+
+    start_email_change(repo, data, id, &customer.email).await
+}
+
+/// Changes the fields that are present, starting an email change when one is given.
+pub async fn patch_customer(
+    repo: &mut (impl CustomerRepo + OutboxRepo),
+    data: &AppData,
+    id: i64,
+    patch: &CustomerPatchRequest,
+) -> Result<()> {
+    repo.patch_customer(id, patch).await?;
+
+    if let Some(email) = &patch.email {
+        start_email_change(repo, data, id, email).await?;
+    }
+
+    Ok(())
+}
+
+/// Returns false, changing nothing, when `current_password` is wrong.
+pub async fn change_password(
+    repo: &mut impl CustomerRepo,
+    id: i64,
+    current_password: &str,
+    new_password: &str,
+) -> Result<bool> {
+    let customer = repo.get_customer(id).await?;
+
+    if customer.password != crate::util::hash(current_password) {
+        return Ok(false);
+    }
+
+    repo.set_customer_password(id, &crate::util::hash(new_password))
+        .await?;
+
+    Ok(true)
+}
+
+/// Stores `new_email` as pending and notifies both addresses. The email itself is only changed
+/// once the link sent to the new address is opened.
+pub async fn start_email_change(
+    repo: &mut (impl CustomerRepo + OutboxRepo),
+    data: &AppData,
+    id: i64,
+    new_email: &str,
+) -> Result<()> {
+    let customer = repo.get_customer(id).await?;
+
+    if customer.email == new_email {
+        return Ok(());
+    }
+
+    if repo.find_customer_by_email(new_email).await?.is_some() {
+        return Err(Error::ConflictError(
+            "the email is already registered".to_owned(),
+        ));
+    }
+
+    let token = crate::util::generate_token();
+    repo.set_pending_email(id, new_email, &token).await?;
+
+    for message in email_change_emails(data, &customer, new_email, &token)? {
+        repo.enqueue_email(&message).await?;
+    }
+
+    Ok(())
+}
+
+/// The confirmation for the new address and the notice, with a cancel link, for the current one.
+fn email_change_emails(
+    data: &AppData,
+    customer: &CustomerResponse,
+    new_email: &str,
+    token: &str,
+) -> Result<[Email; 2]> {
+    let name = &customer.name;
+    let locale = Locale::from_tag(&customer.preferred_locale);
+
+    let confirm_message = data.templates.render(
+        new_email,
+        "email_change_confirm",
+        locale,
+        context! {
+            name,
+            confirm_link => crate::links::email_change_confirm_link(&data.config, token),
+        },
+    )?;
+
+    let notice_message = data.templates.render(
+        &customer.email,
+        "email_change_notice",
+        locale,
+        context! {
+            name,
+            new_email,
+            cancel_link => crate::links::email_change_cancel_link(&data.config, token),
+        },
+    )?;
+
+    Ok([confirm_message, notice_message])
+}
+
+/// The active customer with these credentials, if any.
+pub async fn log_in(
+    repo: &mut impl CustomerRepo,
+    email: &str,
+    password: &str,
+) -> Result<Option<CustomerResponse>> {
+    repo.find_customer_by_login(email, &crate::util::hash(password))
+        .await
+}
+
+/// Queues a reset link to `email`, in the language of its customer. Nothing is sent when no
+/// customer has that email, without telling the caller.
+pub async fn send_password_reset(
+    repo: &mut (impl CustomerRepo + OutboxRepo),
+    data: &AppData,
+    email: &str,
+) -> Result<()> {
+    let Some(customer) = repo.find_customer_by_email(email).await? else {
+        return Ok(());
+    };
+
+    let token = crate::util::generate_token();
+    let ttl_minutes = i64::from(data.config.password_reset_ttl_minutes);
+    repo.set_password_reset_token(customer.id, &token, ttl_minutes)
+        .await?;
+
+    let reset_link = crate::links::password_reset_link(&data.config, &token);
+    let locale = Locale::from_tag(&customer.preferred_locale);
+
+    let message =
+        data.templates
+            .render(email, "password_reset", locale, context! { reset_link })?;
+
+    repo.enqueue_email(&message).await
+}
+
+/// Sets `new_password` on the customer the reset `token` was sent to. Returns whether there was
+/// one, still valid.
+pub async fn reset_password(
+    repo: &mut impl CustomerRepo,
+    token: &str,
+    new_password: &str,
+) -> Result<bool> {
+    repo.reset_customer_password(token, &crate::util::hash(new_password))
+        .await
+}
@@ -0,0 +1,113 @@
+use minijinja::context;
+
+use crate::{
+    AppData, Result,
+    error::Error,
+    order::{OrderItemRequest, OrderPatchRequest, OrderRequest},
+    product::ProductStatus,
+    repository::{CustomerRepo, OrderRepo, OutboxRepo, ProductRepo},
+    templates::Locale,
+};
+
+/// An order stored by [`create_order`].
+pub struct CreatedOrder {
+    pub id: i64,
+    pub total_price: i64,
+}
+
+/// Stores the order and queues its confirmation to the customer. Fails with `RowNotFound` when
+/// one of the products or the customer does not exist or is deleted, and with a conflict when
+/// one of the products is not published.
+pub async fn create_order(
+    repo: &mut (impl ProductRepo + CustomerRepo + OrderRepo + OutboxRepo),
+    data: &AppData,
+    order: &OrderRequest,
+) -> Result<CreatedOrder> {
+    let order_id = repo.insert_order(order.customer_id).await?;
+    repo.set_order_items(order_id, &order.items).await?;
+
+    let mut total_order_price = 0i64;
+    let mut items_context = Vec::new();
+    for item in order.items.iter() {
+        let product = repo.get_product(item.product_id).await?;
+        if product.status != ProductStatus::Published {
+            return Err(Error::ConflictError(format!(
+                "the product {} is not published",
+                product.id
+            )));
+        }
+
+        let total_price = item.amount * product.price;
+        total_order_price += total_price;
+
+        items_context.push(context! {
+            name => product.name,
+            amount => item.amount,
+            total_price,
+        });
+    }
+
+    let customer = repo.get_customer(order.customer_id).await?;
+
+    let message = data.templates.render(
+        &customer.email,
+        "order_confirmation",
+        Locale::from_tag(&customer.preferred_locale),
+        context! {
+            name => customer.name,
+            order_id,
+            total_price => total_order_price,
+            items => items_context,
+        },
+    )?;
+    repo.enqueue_email(&message).await?;
+
+    Ok(CreatedOrder {
+        id: order_id,
+        total_price: total_order_price,
+    })
+}
+
+/// Fails like [`create_order`] when one of the products does not exist or is not published.
+pub async fn update_order(
+    repo: &mut (impl ProductRepo + OrderRepo),
+    id: i64,
+    order: &OrderRequest,
+) -> Result<()> {
+    ensure_published(repo, &order.items).await?;
+
+    repo.set_order_customer(id, order.customer_id).await?;
+    repo.set_order_items(id, &order.items).await
+}
+
+/// Items are checked like in [`update_order`] and replaced as a whole when given.
+pub async fn patch_order(
+    repo: &mut (impl ProductRepo + OrderRepo),
+    id: i64,
+    patch: &OrderPatchRequest,
+) -> Result<()> {
+    if let Some(customer_id) = patch.customer_id {
+        repo.set_order_customer(id, customer_id).await?;
+    }
+
+    if let Some(items) = &patch.items {
+        ensure_published(repo, items).await?;
+        repo.set_order_items(id, items).await?;
+    }
+
+    Ok(())
+}
+
+async fn ensure_published(repo: &mut impl ProductRepo, items: &[OrderItemRequest]) -> Result<()> {
+    for item in items {
+        let product = repo.get_product(item.product_id).await?;
+        if product.status != ProductStatus::Published {
+            return Err(Error::ConflictError(format!(
+                "the product {} is not published",
+                product.id
+            )));
+        }
+    }
+
+    Ok(())
+}
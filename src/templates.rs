@@ -0,0 +1,212 @@
+use minijinja::{Environment, Value, context};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{Config, Result, mailer::Email};
+
+const EMBEDDED_TEMPLATES: &[(&str, &str)] = &[
+    ("layout.html", include_str!("../templates/layout.html")),
+    (
+        "pt-BR/activation.html",
+        include_str!("../templates/pt-BR/activation.html"),
+    ),
+    (
+        "en/activation.html",
+        include_str!("../templates/en/activation.html"),
+    ),
+    (
+        "pt-BR/email_change_confirm.html",
+        include_str!("../templates/pt-BR/email_change_confirm.html"),
+    ),
+    (
+        "en/email_change_confirm.html",
+        include_str!("../templates/en/email_change_confirm.html"),
+    ),
+    (
+        "pt-BR/email_change_notice.html",
+        include_str!("../templates/pt-BR/email_change_notice.html"),
+    ),
+    (
+        "en/email_change_notice.html",
+        include_str!("../templates/en/email_change_notice.html"),
+    ),
+    (
+        "pt-BR/password_reset.html",
+        include_str!("../templates/pt-BR/password_reset.html"),
+    ),
+    (
+        "en/password_reset.html",
+        include_str!("../templates/en/password_reset.html"),
+    ),
+    (
+        "pt-BR/order_confirmation.html",
+        include_str!("../templates/pt-BR/order_confirmation.html"),
+    ),
+    (
+        "en/order_confirmation.html",
+        include_str!("../templates/en/order_confirmation.html"),
+    ),
+];
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "pt-BR")]
+    PtBr,
+    #[serde(rename = "en")]
+    En,
+}
+
+impl Locale {
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::PtBr => "pt-BR",
+            Self::En => "en",
+        }
+    }
+
+    /// Unknown tags fall back to the default locale.
+    pub fn from_tag(tag: &str) -> Self {
+        match tag {
+            "en" => Self::En,
+            _ => Self::PtBr,
+        }
+    }
+}
+
+/// Email templates, rendered per locale as `{locale}/{name}.html` on top of `layout.html`.
+/// Each template sets the subject in a `subject` block. HTML is auto-escaped.
+pub struct Templates {
+    env: Environment<'static>,
+}
+
+impl Templates {
+    /// Uses the templates embedded in the binary, unless `templates_dir` points to a directory
+    /// with the same layout to load them from.
+    pub fn new(config: &Config) -> Result<Self> {
+        let mut env = Environment::new();
+
+        match &config.templates_dir {
+            Some(templates_dir) => env.set_loader(minijinja::path_loader(templates_dir)),
+            None => {
+                for (name, source) in EMBEDDED_TEMPLATES {
+                    env.add_template(name, source)?;
+                }
+            }
+        }
+
+        Ok(Self { env })
+    }
+
+    pub fn render(&self, to: &str, name: &str, locale: Locale, context: Value) -> Result<Email> {
+        let template = self
+            .env
+            .get_template(&format!("{}/{name}.html", locale.tag()))?;
+        let context = context! { locale => locale.tag(), ..context };
+
+        let mut rendered = template.render_captured(&context)?;
+        let subject = rendered.with_state_mut(|state| state.render_block("subject"))?;
+        let html_body = rendered.into_output();
+
+        Ok(Email {
+            to: to.to_owned(),
+            subject: html_to_text(&subject),
+            text_body: html_to_text(&html_body),
+            html_body,
+        })
+    }
+}
+
+/// Plain-text rendering of our own email HTML, used as the alternative part of every message.
+/// Links keep their target next to the text.
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    let mut in_head = false;
+    let mut link_href = None;
+
+    while let Some(tag_start) = rest.find('<') {
+        if !in_head {
+            push_text(&mut text, &rest[..tag_start]);
+        }
+
+        let Some(tag_len) = rest[tag_start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[tag_start + 1..tag_start + tag_len];
+        rest = &rest[tag_start + tag_len + 1..];
+
+        let is_closing = tag.starts_with('/');
+        let tag_name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match (tag_name.as_str(), is_closing) {
+            ("head", _) => in_head = !is_closing,
+            ("br", _) => text.push('\n'),
+            ("p" | "div" | "ul" | "ol" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6", _) => {
+                text.push_str("\n\n")
+            }
+            ("li", false) => text.push_str("\n- "),
+            ("a", false) => link_href = attribute(tag, "href"),
+            ("a", true) => {
+                if let Some(href) = link_href.take() {
+                    text.push_str(&format!(" ({href})"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !in_head {
+        push_text(&mut text, rest);
+    }
+
+    let mut lines = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if !line.is_empty() || lines.last().is_some_and(|last: &&str| !last.is_empty()) {
+            lines.push(line);
+        }
+    }
+
+    lines.join("\n").trim().to_owned()
+}
+
+fn push_text(text: &mut String, raw: &str) {
+    let collapsed = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.is_empty() {
+        return;
+    }
+
+    if raw.starts_with(char::is_whitespace) && !text.ends_with(char::is_whitespace) {
+        text.push(' ');
+    }
+    text.push_str(&unescape(&collapsed));
+    if raw.ends_with(char::is_whitespace) {
+        text.push(' ');
+    }
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let value_start = tag.find(&format!("{name}="))? + name.len() + 1;
+    let quote = tag[value_start..].chars().next()?;
+    let value = &tag[value_start + 1..];
+
+    Some(unescape(&value[..value.find(quote)?]))
+}
+
+fn unescape(escaped: &str) -> String {
+    escaped
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&#x2f;", "/")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
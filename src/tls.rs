@@ -0,0 +1,163 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use actix_web::{
+    HttpRequest, HttpResponse,
+    http::header::{self, HeaderValue},
+};
+use rustls::{
+    ServerConfig,
+    crypto::CryptoProvider,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use tokio::sync::watch;
+
+#[derive(thiserror::Error, Debug)]
+pub enum TlsError {
+    #[error("could not read {path}: {source}")]
+    PemError {
+        path: String,
+        source: rustls::pki_types::pem::Error,
+    },
+    #[error("{0} contains no certificate")]
+    NoCertificateError(String),
+    #[error("invalid TLS certificate or key: {0}")]
+    RustlsError(#[from] rustls::Error),
+}
+
+/// Serves whatever certificate was loaded last, so it can be swapped without restarting.
+#[derive(Debug)]
+pub struct ReloadingCertResolver {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ReloadingCertResolver {
+    pub fn new(cert_path: &str, key_path: &str) -> Result<Self, TlsError> {
+        let cert_path = PathBuf::from(cert_path);
+        let key_path = PathBuf::from(key_path);
+        let certified_key = load_certified_key(&cert_path, &key_path)?;
+
+        Ok(Self {
+            cert_path,
+            key_path,
+            current: RwLock::new(Arc::new(certified_key)),
+        })
+    }
+
+    fn reload(&self) -> Result<(), TlsError> {
+        let certified_key = load_certified_key(&self.cert_path, &self.key_path)?;
+        *self.current.write().unwrap() = Arc::new(certified_key);
+
+        Ok(())
+    }
+
+    fn modified_at(&self) -> Option<(SystemTime, SystemTime)> {
+        let modified_at = |path: &Path| path.metadata().and_then(|metadata| metadata.modified());
+
+        Some((
+            modified_at(&self.cert_path).ok()?,
+            modified_at(&self.key_path).ok()?,
+        ))
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+pub fn server_config(resolver: Arc<ReloadingCertResolver>) -> Result<ServerConfig, TlsError> {
+    let config = ServerConfig::builder_with_provider(Arc::new(provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_cert_resolver(resolver);
+
+    Ok(config)
+}
+
+/// Checks the certificate and key files every `interval` and reloads them when either changes.
+/// A broken pair (e.g. caught halfway through a renewal) is logged and the previous one kept.
+pub async fn watch_certificate(
+    resolver: Arc<ReloadingCertResolver>,
+    interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut last_modified_at = resolver.modified_at();
+
+    while !*shutdown.borrow() {
+        tokio::select! {
+            _ = actix_web::rt::time::sleep(interval) => {}
+            _ = shutdown.changed() => break,
+        }
+
+        let modified_at = resolver.modified_at();
+        if modified_at.is_none() || modified_at == last_modified_at {
+            continue;
+        }
+
+        match resolver.reload() {
+            Ok(()) => {
+                last_modified_at = modified_at;
+                tracing::info!("TLS certificate reloaded");
+            }
+            Err(error) => tracing::warn!(%error, "could not reload the TLS certificate"),
+        }
+    }
+}
+
+/// Response of the plain HTTP listener: the same URL over HTTPS on `https_port`.
+pub fn redirect_to_https(request: &HttpRequest, https_port: u16) -> HttpResponse {
+    let connection_info = request.connection_info();
+    let host = connection_info.host();
+    let host_name = match host.rsplit_once(':') {
+        // Leave IPv6 literals such as [::1] alone
+        Some((host_name, port)) if !port.ends_with(']') => host_name,
+        _ => host,
+    };
+
+    let location = match https_port {
+        443 => format!("https://{host_name}{}", request.uri()),
+        port => format!("https://{host_name}:{port}{}", request.uri()),
+    };
+
+    match HeaderValue::from_str(&location) {
+        Ok(location) => HttpResponse::PermanentRedirect()
+            .insert_header((header::LOCATION, location))
+            .finish(),
+        Err(_) => HttpResponse::BadRequest().finish(),
+    }
+}
+
+fn load_certified_key(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey, TlsError> {
+    let pem_error = |path: &Path| {
+        let path = path.display().to_string();
+        move |source| TlsError::PemError { path, source }
+    };
+
+    let cert_chain = CertificateDer::pem_file_iter(cert_path)
+        .map_err(pem_error(cert_path))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(pem_error(cert_path))?;
+
+    if cert_chain.is_empty() {
+        return Err(TlsError::NoCertificateError(
+            cert_path.display().to_string(),
+        ));
+    }
+
+    let key = PrivateKeyDer::from_pem_file(key_path).map_err(pem_error(key_path))?;
+
+    Ok(CertifiedKey::from_der(cert_chain, key, &provider())?)
+}
+
+fn provider() -> CryptoProvider {
+    rustls::crypto::ring::default_provider()
+}
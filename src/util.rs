@@ -1,31 +1,24 @@
-use lettre::{
-    Message, SmtpTransport, Transport, message::header::ContentType,
-    transport::smtp::authentication::Credentials,
-};
-
-use crate::{Config, Result};
+use rand::{Rng, distr::Alphanumeric};
+use serde::{Deserialize, Deserializer};
 
 pub fn hash(raw: &str) -> String {
     format!("{:x}", md5::compute(raw))
 }
 
-pub fn send_html_email(config: &Config, to: &str, subject: &str, body: String) -> Result<()> {
-    let message = Message::builder()
-        .from(config.smtp_from.parse()?)
-        .to(to.parse()?)
-        .subject(subject)
-        .header(ContentType::TEXT_HTML)
-        .body(body)?;
-
-    let mailer = SmtpTransport::starttls_relay(&config.smtp_host)?
-        .credentials(Credentials::new(
-            config.smtp_user.clone(),
-            config.smtp_password.clone(),
-        ))
-        .port(config.smtp_port)
-        .build();
-
-    mailer.send(&message)?;
+pub fn generate_token() -> String {
+    rand::rng()
+        .sample_iter(Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
 
-    Ok(())
+/// Used on `Option<Option<T>>` fields (with `#[serde(default)]`) so that an explicit `null`
+/// becomes `Some(None)` and can be told apart from an absent field.
+pub fn deserialize_some<'de, T, D>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
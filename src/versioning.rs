@@ -0,0 +1,74 @@
+use std::borrow::Cow;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+    web::Data,
+};
+
+use crate::AppData;
+
+pub const VERSIONS: &[&str] = &["v1", "v2"];
+
+/// Version served under the legacy unversioned `/api` paths.
+pub const LEGACY_VERSION: &str = "v1";
+
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+/// `/api/v1/customer` and `/api/v2/customer` become `/api/customer`, so policies keyed by path
+/// apply to every version.
+pub fn unversioned_path(path: &str) -> Cow<'_, str> {
+    let Some(rest) = path.strip_prefix("/api/") else {
+        return Cow::Borrowed(path);
+    };
+
+    for version in VERSIONS {
+        if let Some(rest) = rest.strip_prefix(version)
+            && (rest.is_empty() || rest.starts_with('/'))
+        {
+            return Cow::Owned(format!("/api{rest}"));
+        }
+    }
+
+    Cow::Borrowed(path)
+}
+
+/// Marks responses of the legacy unversioned routes as deprecated (RFC 9745), with their
+/// `Sunset` (RFC 8594) when one is configured and a link to the versioned route.
+pub async fn deprecate_legacy(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let maybe_data = request.app_data::<Data<AppData>>().cloned();
+    let successor = request
+        .path()
+        .strip_prefix("/api")
+        .map(|rest| format!("</api/{LEGACY_VERSION}{rest}>; rel=\"successor-version\""));
+
+    let mut response = next.call(request).await?;
+    let headers = response.headers_mut();
+
+    let deprecation = maybe_data
+        .as_ref()
+        .and_then(|data| data.config.legacy_api_deprecated_at)
+        .map_or_else(|| "true".to_owned(), |at| format!("@{}", at.timestamp()));
+    if let Ok(deprecation) = HeaderValue::from_str(&deprecation) {
+        headers.insert(DEPRECATION, deprecation);
+    }
+
+    if let Some(sunset_at) = maybe_data.and_then(|data| data.config.legacy_api_sunset_at) {
+        let sunset = sunset_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        if let Ok(sunset) = HeaderValue::from_str(&sunset) {
+            headers.insert(SUNSET, sunset);
+        }
+    }
+
+    if let Some(Ok(successor)) = successor.map(|link| HeaderValue::from_str(&link)) {
+        headers.append(actix_web::http::header::LINK, successor);
+    }
+
+    Ok(response)
+}
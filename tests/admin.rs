@@ -0,0 +1,111 @@
+mod common;
+
+use actix_web::{
+    http::{StatusCode, header},
+    test,
+};
+use iconery_api::repository::CustomerRepo;
+use serde_json::Value;
+
+use common::{TestContext, TestPool, basic_authorization, insert_admin, insert_customer};
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn admin_routes_require_admin_credentials(db_pool: TestPool) {
+    insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let authorization = insert_admin(&db_pool).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    for request in [
+        test::TestRequest::get().uri("/api/v1/admin/email-outbox"),
+        test::TestRequest::post().uri("/api/v1/admin/purge"),
+        test::TestRequest::get().uri("/api/v1/admin/product"),
+        test::TestRequest::post().uri("/api/v1/admin/product/1/restore"),
+        test::TestRequest::post().uri("/api/v1/admin/customer/1/restore"),
+    ] {
+        let response = test::call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            r#"Basic realm="admin""#
+        );
+    }
+
+    for (email, password) in [
+        ("maria@example.com", "senha_maria"),
+        ("admin@iconery.test", "errada"),
+    ] {
+        let request = test::TestRequest::post()
+            .uri("/api/v1/admin/email-outbox/1/retry")
+            .insert_header(basic_authorization(email, password))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{email}");
+    }
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/admin/email-outbox")
+        .insert_header(authorization)
+        .to_request();
+    let emails: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert!(emails.is_empty());
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn promoted_customer_can_use_admin_routes(db_pool: TestPool) {
+    insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", false).await;
+    let context = TestContext::new(db_pool.clone());
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    // What `create-admin` does
+    db_pool
+        .acquire()
+        .await
+        .unwrap()
+        .upsert_admin(
+            "Maria",
+            "maria@example.com",
+            &iconery_api::util::hash("nova_senha"),
+        )
+        .await
+        .unwrap();
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/admin/email-outbox")
+        .insert_header(basic_authorization("maria@example.com", "nova_senha"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn openapi_documents_admin_authentication(db_pool: TestPool) {
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get()
+        .uri("/api/openapi.json")
+        .to_request();
+    let spec: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(
+        spec["components"]["securitySchemes"]["admin_auth"]["scheme"],
+        "basic"
+    );
+
+    let admin_paths: Vec<_> = spec["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .filter(|(path, _)| path.starts_with("/api/v1/admin/"))
+        .collect();
+    assert!(!admin_paths.is_empty());
+    for (path, operations) in admin_paths {
+        for (method, operation) in operations.as_object().unwrap() {
+            assert_eq!(
+                operation["security"],
+                serde_json::json!([{ "admin_auth": [] }]),
+                "{method} {path}"
+            );
+        }
+    }
+}
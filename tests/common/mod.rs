@@ -0,0 +1,197 @@
+//! Harness for the integration tests. The app runs in-process on top of a database created per
+//! test by `#[sqlx::test]`. That is an SQLite file by default. With the `test-mysql` or
+//! `test-postgres` feature it is a database on the server in `DATABASE_URL`, whose user must be
+//! allowed to create databases. Emails go to a `MemoryMailer` once the outbox is delivered.
+#![allow(dead_code)]
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use actix_web::http::header::{self, HeaderName};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use iconery_api::{
+    AppData, Config,
+    customer::CustomerRequest,
+    db::DbPool,
+    mailer::{Email, MailTransport, MemoryMailer},
+    metrics::Metrics,
+    product::{ProductRequest, ProductStatus},
+    rate_limit::RateLimiter,
+    repository::{CustomerRepo, ProductRepo},
+    templates::Templates,
+};
+
+/// Pool of the backend the tests run on, as `#[sqlx::test]` hands it to them.
+#[cfg(feature = "test-mysql")]
+pub type TestPool = sqlx::MySqlPool;
+#[cfg(feature = "test-postgres")]
+pub type TestPool = sqlx::PgPool;
+#[cfg(not(any(feature = "test-mysql", feature = "test-postgres")))]
+pub type TestPool = sqlx::SqlitePool;
+
+/// Migrations of the backend the tests run on, for `#[sqlx::test(migrator = ...)]`.
+#[cfg(feature = "test-mysql")]
+pub use iconery_api::db::MYSQL_MIGRATOR as MIGRATOR;
+#[cfg(feature = "test-postgres")]
+pub use iconery_api::db::POSTGRES_MIGRATOR as MIGRATOR;
+#[cfg(not(any(feature = "test-mysql", feature = "test-postgres")))]
+pub use iconery_api::db::SQLITE_MIGRATOR as MIGRATOR;
+
+#[cfg(feature = "test-mysql")]
+fn into_db_pool(db_pool: TestPool) -> DbPool {
+    DbPool::MySql(db_pool)
+}
+
+#[cfg(feature = "test-postgres")]
+fn into_db_pool(db_pool: TestPool) -> DbPool {
+    DbPool::Postgres(db_pool)
+}
+
+#[cfg(not(any(feature = "test-mysql", feature = "test-postgres")))]
+fn into_db_pool(db_pool: TestPool) -> DbPool {
+    DbPool::Sqlite(db_pool)
+}
+
+pub const PUBLIC_BASE_URL: &str = "https://api.iconery.test";
+pub const FRONTEND_BASE_URL: &str = "https://iconery.test";
+
+pub fn test_config() -> Config {
+    Config {
+        mail_transport: MailTransport::Memory,
+        smtp_from: "Iconery <noreply@iconery.test>".to_owned(),
+        public_base_url: Some(PUBLIC_BASE_URL.to_owned()),
+        frontend_base_url: Some(FRONTEND_BASE_URL.to_owned()),
+        // Tests hit the limited routes far more often than any user would
+        rate_limits: BTreeMap::new(),
+        ..Config::default()
+    }
+}
+
+pub struct TestContext {
+    pub data: AppData,
+    pub mailer: Arc<MemoryMailer>,
+}
+
+impl TestContext {
+    pub fn new(db_pool: TestPool) -> Self {
+        Self::with_config(db_pool, test_config())
+    }
+
+    pub fn with_config(db_pool: TestPool, config: Config) -> Self {
+        let db_pool = into_db_pool(db_pool);
+        let mailer = Arc::new(MemoryMailer::default());
+        let rate_limiter = Arc::new(RateLimiter::from_config(&config, &db_pool));
+        let templates = Arc::new(Templates::new(&config).expect("templates should load"));
+
+        let data = AppData {
+            config: Arc::new(config),
+            db_pool,
+            mailer: mailer.clone(),
+            mail_status: Arc::default(),
+            metrics: Arc::new(Metrics::default()),
+            rate_limiter,
+            templates,
+        };
+
+        Self { data, mailer }
+    }
+
+    /// Runs the outbox until nothing is due and returns what was sent, oldest first.
+    pub async fn deliver_emails(&self) -> Vec<Email> {
+        while iconery_api::outbox::deliver_next(&self.data)
+            .await
+            .expect("outbox delivery should succeed")
+        {}
+
+        let sent = self.mailer.sent();
+        self.mailer.clear();
+        sent
+    }
+}
+
+pub async fn insert_product(db_pool: &TestPool, name: &str, price: i64, is_featured: bool) -> i64 {
+    let product = ProductRequest {
+        name: name.to_owned(),
+        description: Some(format!("Descrição de {name}")),
+        price,
+        is_featured,
+        status: Some(ProductStatus::Published),
+        publish_at: None,
+        unpublish_at: None,
+    };
+
+    db_pool
+        .acquire()
+        .await
+        .unwrap()
+        .insert_product(&product)
+        .await
+        .expect("product should be inserted")
+}
+
+pub async fn insert_customer(
+    db_pool: &TestPool,
+    name: &str,
+    email: &str,
+    password: &str,
+    is_active: bool,
+) -> i64 {
+    let customer = CustomerRequest {
+        name: name.to_owned(),
+        email: email.to_owned(),
+        password: password.to_owned(),
+        phone_number: None,
+        address: None,
+        is_active: Some(is_active),
+        preferred_locale: None,
+    };
+
+    db_pool
+        .acquire()
+        .await
+        .unwrap()
+        .insert_customer(&customer, &iconery_api::util::hash(password))
+        .await
+        .expect("customer should be inserted")
+}
+
+/// Active admin account for the `/admin` routes, with the header to authenticate as it.
+pub async fn insert_admin(db_pool: &TestPool) -> (HeaderName, String) {
+    db_pool
+        .acquire()
+        .await
+        .unwrap()
+        .upsert_admin(
+            "Admin",
+            "admin@iconery.test",
+            &iconery_api::util::hash("senha_admin"),
+        )
+        .await
+        .expect("admin should be inserted");
+
+    basic_authorization("admin@iconery.test", "senha_admin")
+}
+
+pub fn basic_authorization(email: &str, password: &str) -> (HeaderName, String) {
+    let credentials = BASE64_STANDARD.encode(format!("{email}:{password}"));
+
+    (header::AUTHORIZATION, format!("Basic {credentials}"))
+}
+
+/// The first link in `email` starting with `prefix`, read from the text body where links come
+/// unescaped as ` (href)` after their text.
+pub fn find_link(email: &Email, prefix: &str) -> String {
+    let start = email
+        .text_body
+        .find(&format!("({prefix}"))
+        .unwrap_or_else(|| panic!("no link to {prefix} in {:?}", email.subject))
+        + 1;
+    let link = &email.text_body[start..];
+
+    link[..link.find(')').expect("link should be closed")].to_owned()
+}
+
+/// Path of a link into the API, to be requested on the test app.
+pub fn api_path(link: &str) -> &str {
+    link.strip_prefix(PUBLIC_BASE_URL)
+        .unwrap_or_else(|| panic!("{link} is not an API link"))
+}
@@ -0,0 +1,833 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use iconery_api::mailer::Email;
+use serde_json::{Value, json};
+
+use common::{
+    FRONTEND_BASE_URL, PUBLIC_BASE_URL, TestContext, TestPool, api_path, find_link, insert_admin,
+    insert_customer,
+};
+
+fn email_to<'e>(emails: &'e [Email], to: &str) -> &'e Email {
+    emails
+        .iter()
+        .find(|email| email.to == to)
+        .unwrap_or_else(|| panic!("no email to {to}"))
+}
+
+/// Token of the link in a password reset email.
+fn reset_token(email: &Email) -> String {
+    let link = find_link(
+        email,
+        &format!("{FRONTEND_BASE_URL}/reset-password-form?token="),
+    );
+
+    link.rsplit_once('=').unwrap().1.to_owned()
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn signup_sends_activation_email(db_pool: TestPool) {
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/customer")
+        .set_json(json!({
+            "name": "Maria Santos",
+            "email": "maria@example.com",
+            "password": "senha_maria",
+            "phone_number": "21988880002",
+            "address": null,
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let emails = context.deliver_emails().await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].to, "maria@example.com");
+    assert_eq!(emails[0].subject, "Confirmação de novo cadastro");
+    assert!(
+        emails[0]
+            .text_body
+            .contains("Seja bem-vindo(a), Maria Santos!")
+    );
+    assert!(emails[0].html_body.contains("Maria Santos"));
+
+    let link = find_link(&emails[0], PUBLIC_BASE_URL);
+    let token = link
+        .strip_prefix(&format!("{PUBLIC_BASE_URL}/api/v1/customer/activate/"))
+        .unwrap();
+    assert_eq!(token.len(), 32);
+    assert_ne!(token, iconery_api::util::hash("maria@example.com"));
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/customer")
+        .to_request();
+    let customers: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customers.len(), 1);
+    assert_eq!(customers[0]["is_active"], false);
+    assert_eq!(customers[0]["preferred_locale"], "pt-BR");
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn signup_uses_preferred_locale(db_pool: TestPool) {
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/customer")
+        .set_json(json!({
+            "name": "Jane",
+            "email": "jane@example.com",
+            "password": "secret",
+            "phone_number": null,
+            "address": null,
+            "preferred_locale": "en",
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let emails = context.deliver_emails().await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].subject, "Confirm your new account");
+    assert!(emails[0].text_body.contains("Welcome, Jane!"));
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn activation_link_activates_and_allows_login(db_pool: TestPool) {
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/customer")
+        .set_json(json!({
+            "name": "Ana Costa",
+            "email": "ana@example.com",
+            "password": "senha_ana",
+            "phone_number": null,
+            "address": null,
+        }))
+        .to_request();
+    test::call_service(&app, request).await;
+
+    // Not active yet
+    let request = test::TestRequest::get()
+        .uri("/api/v1/customer/login")
+        .set_json(json!({ "email": "ana@example.com", "password": "senha_ana" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let emails = context.deliver_emails().await;
+    let link = find_link(&emails[0], PUBLIC_BASE_URL);
+
+    let request = test::TestRequest::get().uri(api_path(&link)).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(test::read_body(response).await, "Cliente ativado!");
+
+    // The link only works once
+    let request = test::TestRequest::get().uri(api_path(&link)).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/customer/login")
+        .set_json(json!({ "email": "ana@example.com", "password": "senha_ana" }))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customer["name"], "Ana Costa");
+    assert_eq!(customer["is_active"], true);
+    assert_eq!(
+        customer["password"],
+        iconery_api::util::hash("senha_ana").as_str()
+    );
+
+    let request = test::TestRequest::post()
+        .uri("/api/v2/customer/login")
+        .set_json(json!({ "email": "ana@example.com", "password": "senha_ana" }))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customer["name"], "Ana Costa");
+    assert!(customer.get("password").is_none());
+
+    let request = test::TestRequest::post()
+        .uri("/api/v2/customer/login")
+        .set_json(json!({ "email": "ana@example.com", "password": "errada" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn invalid_activation_token_is_not_found(db_pool: TestPool) {
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/customer/activate/nope")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn repeated_signup_resends_activation_only(db_pool: TestPool) {
+    insert_customer(&db_pool, "Pedro", "pedro@example.com", "senha_pedro", false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/customer")
+        .set_json(json!({
+            "name": "Outro Nome",
+            "email": "pedro@example.com",
+            "password": "outra_senha",
+            "phone_number": null,
+            "address": null,
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let emails = context.deliver_emails().await;
+    assert_eq!(emails.len(), 1);
+    assert!(emails[0].text_body.contains("Pedro!"));
+
+    let link = find_link(&emails[0], PUBLIC_BASE_URL);
+    let request = test::TestRequest::get().uri(api_path(&link)).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The account keeps its name and password, and the resent link activates it
+    let request = test::TestRequest::get()
+        .uri("/api/v1/customer")
+        .to_request();
+    let customers: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customers.len(), 1);
+    assert_eq!(customers[0]["name"], "Pedro");
+    assert_eq!(customers[0]["is_active"], true);
+    assert_eq!(
+        customers[0]["password"],
+        iconery_api::util::hash("senha_pedro").as_str()
+    );
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn signup_with_active_email_conflicts(db_pool: TestPool) {
+    insert_customer(&db_pool, "Lucas", "lucas@example.com", "senha_lucas", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/customer")
+        .set_json(json!({
+            "name": "Lucas",
+            "email": "lucas@example.com",
+            "password": "senha",
+            "phone_number": null,
+            "address": null,
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert!(context.deliver_emails().await.is_empty());
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn emails_match_regardless_of_case(db_pool: TestPool) {
+    insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/customer")
+        .set_json(json!({
+            "name": "Maria",
+            "email": "MARIA@example.com",
+            "password": "senha",
+            "phone_number": null,
+            "address": null,
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let request = test::TestRequest::post()
+        .uri("/api/v2/customer/login")
+        .set_json(json!({ "email": "Maria@Example.com", "password": "senha_maria" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn gets_customers_per_version(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Lucas", "lucas@example.com", "senha_lucas", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/customer/{id}"))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customer["email"], "lucas@example.com");
+    assert!(customer.get("password").is_some());
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v2/customer/{id}"))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(
+        customer,
+        json!({
+            "id": id,
+            "name": "Lucas",
+            "email": "lucas@example.com",
+            "phone_number": null,
+            "address": null,
+            "is_active": true,
+            "preferred_locale": "pt-BR",
+        })
+    );
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/customer")
+        .to_request();
+    let customers: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customers, [customer]);
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/customer/404")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn updates_customer(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::put()
+        .uri(&format!("/api/v1/customer/{id}"))
+        .set_json(json!({
+            "name": "Maria Santos",
+            "email": "maria@example.com",
+            "password": "nova_senha",
+            "phone_number": "21988880002",
+            "address": "Av. Rio Branco, 123",
+            "preferred_locale": "en",
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Same email, so nothing to confirm
+    assert!(context.deliver_emails().await.is_empty());
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/customer/{id}"))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(
+        customer,
+        json!({
+            "id": id,
+            "name": "Maria Santos",
+            "email": "maria@example.com",
+            "password": iconery_api::util::hash("nova_senha"),
+            "phone_number": "21988880002",
+            "address": "Av. Rio Branco, 123",
+            "is_active": true,
+            "preferred_locale": "en",
+        })
+    );
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn patches_only_given_fields(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/customer/{id}"))
+        .set_json(json!({ "phone_number": "21988880002", "address": "Av. Rio Branco, 123" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/customer/{id}"))
+        .set_json(json!({ "name": "Maria Santos", "address": null }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v2/customer/{id}"))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customer["name"], "Maria Santos");
+    assert_eq!(customer["email"], "maria@example.com");
+    assert_eq!(customer["phone_number"], "21988880002");
+    assert_eq!(customer["address"], Value::Null);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn changes_password_with_current_one(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::put()
+        .uri(&format!("/api/v1/customer/{id}/password"))
+        .set_json(json!({ "current_password": "errada", "new_password": "nova_senha" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(test::read_body(response).await, "Senha atual incorreta");
+
+    let request = test::TestRequest::put()
+        .uri(&format!("/api/v1/customer/{id}/password"))
+        .set_json(json!({ "current_password": "senha_maria", "new_password": "nova_senha" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        test::read_body(response).await,
+        "Senha alterada com sucesso"
+    );
+
+    let request = test::TestRequest::post()
+        .uri("/api/v2/customer/login")
+        .set_json(json!({ "email": "maria@example.com", "password": "nova_senha" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn email_change_is_applied_once_confirmed(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/api/v1/customer/{id}/email"))
+        .set_json(json!({ "email": "maria.santos@example.com" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let emails = context.deliver_emails().await;
+    assert_eq!(emails.len(), 2);
+
+    let confirm_email = email_to(&emails, "maria.santos@example.com");
+    assert_eq!(confirm_email.subject, "Confirmação de alteração de email");
+    let confirm_link = find_link(
+        confirm_email,
+        &format!("{PUBLIC_BASE_URL}/api/v1/customer/confirm-email/"),
+    );
+
+    let notice_email = email_to(&emails, "maria@example.com");
+    assert_eq!(notice_email.subject, "Alteração de email solicitada");
+    assert!(notice_email.text_body.contains("maria.santos@example.com"));
+    find_link(
+        notice_email,
+        &format!("{PUBLIC_BASE_URL}/api/v1/customer/cancel-email/"),
+    );
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/customer/reset-password/maria@example.com")
+        .to_request();
+    test::call_service(&app, request).await;
+    let old_reset_token = reset_token(&context.deliver_emails().await[0]);
+
+    // Still the old email until confirmed
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v2/customer/{id}"))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customer["email"], "maria@example.com");
+
+    let request = test::TestRequest::get()
+        .uri(api_path(&confirm_link))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        test::read_body(response).await,
+        "Email alterado com sucesso"
+    );
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v2/customer/{id}"))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customer["email"], "maria.santos@example.com");
+
+    // Tokens are single use
+    let request = test::TestRequest::get()
+        .uri(api_path(&confirm_link))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(test::read_body(response).await, "Token inválido");
+
+    // Reset links follow the new email, the one sent to the old email stops working
+    let request = test::TestRequest::get()
+        .uri("/api/v1/customer/reset-password/maria.santos@example.com")
+        .to_request();
+    test::call_service(&app, request).await;
+    let emails = context.deliver_emails().await;
+    assert_eq!(emails[0].to, "maria.santos@example.com");
+
+    for (token, status) in [
+        (old_reset_token, StatusCode::NOT_FOUND),
+        (reset_token(&emails[0]), StatusCode::OK),
+    ] {
+        let request = test::TestRequest::post()
+            .uri(&format!("/api/v1/customer/reset-password/{token}"))
+            .set_json("nova_senha")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), status);
+    }
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn email_change_is_cancelled_from_notice(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/customer/{id}"))
+        .set_json(json!({ "email": "intruso@example.com" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let emails = context.deliver_emails().await;
+    let confirm_link = find_link(
+        email_to(&emails, "intruso@example.com"),
+        &format!("{PUBLIC_BASE_URL}/api/v1/customer/confirm-email/"),
+    );
+    let cancel_link = find_link(
+        email_to(&emails, "maria@example.com"),
+        &format!("{PUBLIC_BASE_URL}/api/v1/customer/cancel-email/"),
+    );
+
+    let request = test::TestRequest::get()
+        .uri(api_path(&cancel_link))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        test::read_body(response).await,
+        "Alteração de email cancelada"
+    );
+
+    let request = test::TestRequest::get()
+        .uri(api_path(&confirm_link))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v2/customer/{id}"))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customer["email"], "maria@example.com");
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn email_change_is_cancelled_by_id(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/api/v1/customer/{id}/email"))
+        .set_json(json!({ "email": "maria.santos@example.com" }))
+        .to_request();
+    test::call_service(&app, request).await;
+
+    let emails = context.deliver_emails().await;
+    let confirm_link = find_link(
+        email_to(&emails, "maria.santos@example.com"),
+        &format!("{PUBLIC_BASE_URL}/api/v1/customer/confirm-email/"),
+    );
+
+    let request = test::TestRequest::delete()
+        .uri(&format!("/api/v1/customer/{id}/email"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(api_path(&confirm_link))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(test::read_body(response).await, "Token inválido");
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/customer/cancel-email/nope")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn email_change_to_taken_email_conflicts(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    insert_customer(&db_pool, "Ana", "ana@example.com", "senha_ana", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/api/v1/customer/{id}/email"))
+        .set_json(json!({ "email": "ana@example.com" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert!(context.deliver_emails().await.is_empty());
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn password_reset_flow(db_pool: TestPool) {
+    insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/customer/reset-password/maria@example.com")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let emails = context.deliver_emails().await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].to, "maria@example.com");
+    assert_eq!(emails[0].subject, "Redefinição de senha");
+
+    let token = reset_token(&emails[0]);
+    assert_eq!(token.len(), 32);
+    assert_ne!(token, iconery_api::util::hash("maria@example.com"));
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/api/v1/customer/reset-password/{token}"))
+        .set_json("nova_senha")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        test::read_body(response).await,
+        "Senha redefinida com sucesso"
+    );
+
+    let request = test::TestRequest::post()
+        .uri("/api/v2/customer/login")
+        .set_json(json!({ "email": "maria@example.com", "password": "nova_senha" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The link only works once
+    let request = test::TestRequest::post()
+        .uri(&format!("/api/v1/customer/reset-password/{token}"))
+        .set_json("outra_senha")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/customer/reset-password/nope")
+        .set_json("outra_senha")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(test::read_body(response).await, "Token inválido");
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn expired_password_reset_is_not_found(db_pool: TestPool) {
+    insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let mut config = common::test_config();
+    config.password_reset_ttl_minutes = 0;
+    let context = TestContext::with_config(db_pool, config);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/customer/reset-password/maria@example.com")
+        .to_request();
+    test::call_service(&app, request).await;
+    let token = reset_token(&context.deliver_emails().await[0]);
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/api/v1/customer/reset-password/{token}"))
+        .set_json("nova_senha")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn password_reset_for_unknown_email_sends_nothing(db_pool: TestPool) {
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/customer/reset-password/ninguem@example.com")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(context.deliver_emails().await.is_empty());
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn deletes_customer(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::delete()
+        .uri(&format!("/api/v1/customer/{id}"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/customer/{id}"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn deleted_customer_is_hidden_until_restored(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let authorization = insert_admin(&db_pool).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::delete()
+        .uri(&format!("/api/v1/customer/{id}"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri("/api/v2/customer")
+        .to_request();
+    let customers: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert!(customers.iter().all(|customer| customer["id"] != id));
+
+    let login = json!({ "email": "maria@example.com", "password": "senha_maria" });
+    let request = test::TestRequest::post()
+        .uri("/api/v2/customer/login")
+        .set_json(&login)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // The email stays taken while the customer can still be restored
+    let request = test::TestRequest::post()
+        .uri("/api/v1/customer")
+        .set_json(json!({
+            "name": "Maria",
+            "email": "maria@example.com",
+            "password": "outra_senha",
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/api/v1/admin/customer/{id}/restore"))
+        .insert_header(authorization)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::post()
+        .uri("/api/v2/customer/login")
+        .set_json(&login)
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customer["id"], id);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn login_is_rate_limited(db_pool: TestPool) {
+    let mut config = common::test_config();
+    config.rate_limits = iconery_api::rate_limit::default_policies();
+    let context = TestContext::with_config(db_pool, config);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    // Five attempts per email, then the bucket is empty
+    for _ in 0..5 {
+        let request = test::TestRequest::post()
+            .uri("/api/v2/customer/login")
+            .set_json(json!({ "email": "maria@example.com", "password": "errada" }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    let request = test::TestRequest::post()
+        .uri("/api/v2/customer/login")
+        .set_json(json!({ "email": "maria@example.com", "password": "errada" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn rejected_login_keeps_the_other_buckets(db_pool: TestPool) {
+    use iconery_api::rate_limit::{RateLimitKey, RateLimitPolicy};
+
+    let mut config = common::test_config();
+    config.rate_limits = [(
+        "login".to_owned(),
+        vec![
+            RateLimitPolicy {
+                key: RateLimitKey::Ip,
+                capacity: 2,
+                refill_secs: 3600,
+            },
+            RateLimitPolicy {
+                key: RateLimitKey::Email,
+                capacity: 1,
+                refill_secs: 3600,
+            },
+        ],
+    )]
+    .into();
+    let context = TestContext::with_config(db_pool, config);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    // The second attempt for maria is rejected by her email bucket without taking from the IP
+    // bucket, which still has room for ana
+    for (email, status) in [
+        ("maria@example.com", StatusCode::NOT_FOUND),
+        ("maria@example.com", StatusCode::TOO_MANY_REQUESTS),
+        ("ana@example.com", StatusCode::NOT_FOUND),
+        ("joao@example.com", StatusCode::TOO_MANY_REQUESTS),
+    ] {
+        let request = test::TestRequest::post()
+            .uri("/api/v2/customer/login")
+            .set_json(json!({ "email": email, "password": "errada" }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), status, "{email}");
+    }
+}
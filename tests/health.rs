@@ -0,0 +1,42 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use iconery_api::{Config, mailer::MailTransport};
+use serde_json::Value;
+
+use common::{TestContext, TestPool, test_config};
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn ready_probes_the_mail_transport(db_pool: TestPool) {
+    let config = Config {
+        mail_transport: MailTransport::File,
+        mail_dir: "/nonexistent/mail".to_owned(),
+        ..test_config()
+    };
+    let mut context = TestContext::with_config(db_pool, config);
+    // The harness always delivers to memory
+    context.data.mailer = iconery_api::mailer::from_config(&context.data.config).unwrap();
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get().uri("/ready").to_request();
+    let body: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(body["checks"]["mail"]["detail"], "not checked yet");
+
+    // `/ready` reports what the background probe found last
+    iconery_api::health::probe_mail(&context.data).await;
+
+    let request = test::TestRequest::get().uri("/ready").to_request();
+    let response = test::call_service(&app, request).await;
+    // Mail is not critical, emails wait in the outbox
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: Value = test::read_body_json(response).await;
+    assert_eq!(body["checks"]["database"]["status"], "up");
+    assert_eq!(body["checks"]["mail"]["status"], "down");
+    assert!(
+        body["checks"]["mail"]["detail"]
+            .as_str()
+            .unwrap()
+            .starts_with("/nonexistent/mail")
+    );
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use actix_web::test;
+
+use common::{TestContext, TestPool};
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn metrics_time_connections_taken_by_requests(db_pool: TestPool) {
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get().uri("/api/v1/product").to_request();
+    test::call_service(&app, request).await;
+
+    let request = test::TestRequest::get().uri("/metrics").to_request();
+    let body = test::call_and_read_body(&app, request).await;
+    let body = std::str::from_utf8(&body).unwrap();
+
+    let acquired = body
+        .lines()
+        .find_map(|line| line.strip_prefix("iconery_db_pool_acquire_seconds_count "))
+        .expect("the acquire histogram should be rendered");
+    assert!(acquired.parse::<u64>().unwrap() >= 1);
+    assert!(!body.contains("probe"));
+}
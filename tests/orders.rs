@@ -0,0 +1,452 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::{Value, json};
+
+use common::{TestContext, TestPool, insert_customer, insert_product};
+
+/// Amounts of the order's items by product id, sorted.
+fn item_amounts(order: &Value) -> Vec<(i64, i64)> {
+    let mut amounts = order["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| {
+            (
+                item["product_id"].as_i64().unwrap(),
+                item["amount"].as_i64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    amounts.sort();
+    amounts
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn creates_order_and_sends_confirmation(db_pool: TestPool) {
+    let customer_id =
+        insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let potion_id = insert_product(&db_pool, "Poção de Vida", 3, true).await;
+    let coins_id = insert_product(&db_pool, "Duas Moedas", 100, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/order")
+        .set_json(json!({
+            "customer_id": customer_id,
+            "items": [
+                { "product_id": potion_id, "amount": 2 },
+                { "product_id": coins_id, "amount": 5 },
+            ],
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get().uri("/api/v1/order").to_request();
+    let orders: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0]["customer_id"], customer_id);
+    assert_eq!(item_amounts(&orders[0]), [(potion_id, 2), (coins_id, 5)]);
+    let order_id = orders[0]["id"].as_i64().unwrap();
+
+    let emails = context.deliver_emails().await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].to, "maria@example.com");
+    assert_eq!(emails[0].subject, "Confirmação de pedido");
+    assert!(emails[0].text_body.contains(&format!(
+        "Olá, Maria! Seu pedido (ID: {order_id}) foi recebido."
+    )));
+    assert!(emails[0].text_body.contains("Total: R$506"));
+    assert!(
+        emails[0]
+            .text_body
+            .contains("- Nome do produto: Poção de Vida - quantidade: 2 - preço total: R$6")
+    );
+    assert!(
+        emails[0]
+            .text_body
+            .contains("- Nome do produto: Duas Moedas - quantidade: 5 - preço total: R$500")
+    );
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn order_for_missing_product_is_rolled_back(db_pool: TestPool) {
+    let customer_id =
+        insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/order")
+        .set_json(json!({
+            "customer_id": customer_id,
+            "items": [{ "product_id": 404, "amount": 1 }],
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert!(!response.status().is_success());
+
+    let request = test::TestRequest::get().uri("/api/v1/order").to_request();
+    let orders: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert!(orders.is_empty());
+    assert!(context.deliver_emails().await.is_empty());
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn gets_order(db_pool: TestPool) {
+    let customer_id =
+        insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let product_id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/order")
+        .set_json(json!({
+            "customer_id": customer_id,
+            "items": [{ "product_id": product_id, "amount": 1 }],
+        }))
+        .to_request();
+    test::call_service(&app, request).await;
+
+    let request = test::TestRequest::get().uri("/api/v1/order").to_request();
+    let orders: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    let order_id = orders[0]["id"].as_i64().unwrap();
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .to_request();
+    let order: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(order, orders[0]);
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/order/404")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn updates_order(db_pool: TestPool) {
+    let maria_id =
+        insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let ana_id = insert_customer(&db_pool, "Ana", "ana@example.com", "senha_ana", true).await;
+    let helm_id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let shield_id = insert_product(&db_pool, "Escudo", 2, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/order")
+        .set_json(json!({
+            "customer_id": maria_id,
+            "items": [{ "product_id": helm_id, "amount": 1 }],
+        }))
+        .to_request();
+    test::call_service(&app, request).await;
+
+    let request = test::TestRequest::get().uri("/api/v1/order").to_request();
+    let orders: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    let order_id = orders[0]["id"].as_i64().unwrap();
+
+    let request = test::TestRequest::put()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .set_json(json!({
+            "customer_id": ana_id,
+            "items": [{ "product_id": shield_id, "amount": 2 }],
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .to_request();
+    let order: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(order["customer_id"], ana_id);
+    assert_eq!(item_amounts(&order), [(shield_id, 2)]);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn patches_order(db_pool: TestPool) {
+    let maria_id =
+        insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let ana_id = insert_customer(&db_pool, "Ana", "ana@example.com", "senha_ana", true).await;
+    let helm_id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let shield_id = insert_product(&db_pool, "Escudo", 2, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/order")
+        .set_json(json!({
+            "customer_id": maria_id,
+            "items": [{ "product_id": helm_id, "amount": 1 }],
+        }))
+        .to_request();
+    test::call_service(&app, request).await;
+
+    let request = test::TestRequest::get().uri("/api/v1/order").to_request();
+    let orders: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    let order_id = orders[0]["id"].as_i64().unwrap();
+
+    // Without items they are kept
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .set_json(json!({ "customer_id": ana_id }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .to_request();
+    let order: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(order["customer_id"], ana_id);
+    assert_eq!(item_amounts(&order), [(helm_id, 1)]);
+
+    // With items they are replaced as a whole
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .set_json(json!({ "items": [{ "product_id": shield_id, "amount": 3 }] }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .to_request();
+    let order: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(order["customer_id"], ana_id);
+    assert_eq!(item_amounts(&order), [(shield_id, 3)]);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn deletes_order(db_pool: TestPool) {
+    let customer_id =
+        insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let product_id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/order")
+        .set_json(json!({
+            "customer_id": customer_id,
+            "items": [{ "product_id": product_id, "amount": 1 }],
+        }))
+        .to_request();
+    test::call_service(&app, request).await;
+
+    let request = test::TestRequest::get().uri("/api/v1/order").to_request();
+    let orders: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    let order_id = orders[0]["id"].as_i64().unwrap();
+
+    let request = test::TestRequest::delete()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn lists_orders_by_customer(db_pool: TestPool) {
+    let maria_id =
+        insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let ana_id = insert_customer(&db_pool, "Ana", "ana@example.com", "senha_ana", true).await;
+    let product_id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    for (customer_id, amount) in [(maria_id, 1), (ana_id, 2), (maria_id, 3)] {
+        let request = test::TestRequest::post()
+            .uri("/api/v1/order")
+            .set_json(json!({
+                "customer_id": customer_id,
+                "items": [{ "product_id": product_id, "amount": amount }],
+            }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/order/customer/{maria_id}"))
+        .to_request();
+    let orders: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    let mut amounts = orders
+        .iter()
+        .inspect(|order| assert_eq!(order["customer_id"], maria_id))
+        .flat_map(item_amounts)
+        .collect::<Vec<_>>();
+    amounts.sort();
+    assert_eq!(amounts, [(product_id, 1), (product_id, 3)]);
+
+    let emails = context.deliver_emails().await;
+    assert_eq!(emails.len(), 3);
+    assert_eq!(
+        emails
+            .iter()
+            .filter(|email| email.to == "maria@example.com")
+            .count(),
+        2
+    );
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn deleted_product_and_customer_stay_in_orders(db_pool: TestPool) {
+    let customer_id =
+        insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let product_id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let order = json!({
+        "customer_id": customer_id,
+        "items": [{ "product_id": product_id, "amount": 1 }],
+    });
+    let request = test::TestRequest::post()
+        .uri("/api/v1/order")
+        .set_json(&order)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for uri in [
+        format!("/api/v1/product/{product_id}"),
+        format!("/api/v1/customer/{customer_id}"),
+    ] {
+        let request = test::TestRequest::delete().uri(&uri).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/order/customer/{customer_id}"))
+        .to_request();
+    let orders: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(item_amounts(&orders[0]), [(product_id, 1)]);
+    assert_eq!(orders[0]["items"][0]["product_name"], "Elmo");
+    assert_eq!(orders[0]["items"][0]["product_price"], 10);
+
+    // But they cannot be ordered anymore
+    let request = test::TestRequest::post()
+        .uri("/api/v1/order")
+        .set_json(&order)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn unpublished_product_cannot_be_ordered(db_pool: TestPool) {
+    let customer_id =
+        insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let product_id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/product/{product_id}"))
+        .set_json(json!({ "status": "archived" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/order")
+        .set_json(json!({
+            "customer_id": customer_id,
+            "items": [{ "product_id": product_id, "amount": 1 }],
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let request = test::TestRequest::get().uri("/api/v1/order").to_request();
+    let orders: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert!(orders.is_empty());
+    assert!(context.deliver_emails().await.is_empty());
+}
+
+/// Customer with an order for a published product, and an archived product, both by id.
+async fn order_and_archived_product(db_pool: &TestPool) -> (i64, i64, i64) {
+    let customer_id =
+        insert_customer(db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let helm_id = insert_product(db_pool, "Elmo", 10, false).await;
+    let shield_id = insert_product(db_pool, "Escudo", 2, false).await;
+    let context = TestContext::new(db_pool.clone());
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/order")
+        .set_json(json!({
+            "customer_id": customer_id,
+            "items": [{ "product_id": helm_id, "amount": 1 }],
+        }))
+        .to_request();
+    test::call_service(&app, request).await;
+
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/product/{shield_id}"))
+        .set_json(json!({ "status": "archived" }))
+        .to_request();
+    test::call_service(&app, request).await;
+
+    let request = test::TestRequest::get().uri("/api/v1/order").to_request();
+    let orders: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+
+    (customer_id, orders[0]["id"].as_i64().unwrap(), shield_id)
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn unpublished_product_cannot_be_put_in_an_order(db_pool: TestPool) {
+    let (customer_id, order_id, archived_id) = order_and_archived_product(&db_pool).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::put()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .set_json(json!({
+            "customer_id": customer_id,
+            "items": [{ "product_id": archived_id, "amount": 2 }],
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .to_request();
+    let order: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(order["items"][0]["product_name"], "Elmo");
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn unpublished_product_cannot_be_patched_into_an_order(db_pool: TestPool) {
+    let (_, order_id, archived_id) = order_and_archived_product(&db_pool).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .set_json(json!({ "items": [{ "product_id": archived_id, "amount": 2 }] }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/order/{order_id}"))
+        .to_request();
+    let order: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(order["items"][0]["product_name"], "Elmo");
+}
@@ -0,0 +1,422 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::{Value, json};
+
+use common::{TestContext, TestPool, insert_admin, insert_product};
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn creates_and_gets_product(db_pool: TestPool) {
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/product")
+        .set_json(json!({
+            "name": "Elmo",
+            "description": "Elmo de ferro",
+            "price": 10,
+            "is_featured": true,
+            "status": "published",
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get().uri("/api/v1/product").to_request();
+    let products: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(products.len(), 1);
+    let id = products[0]["id"].as_i64().unwrap();
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/product/{id}"))
+        .to_request();
+    let product: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(
+        product,
+        json!({
+            "id": id,
+            "name": "Elmo",
+            "description": "Elmo de ferro",
+            "price": 10,
+            "is_featured": true,
+            "status": "published",
+            "publish_at": null,
+            "unpublish_at": null,
+        })
+    );
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn missing_product_is_not_found(db_pool: TestPool) {
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/product/404")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn updates_product(db_pool: TestPool) {
+    let id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::put()
+        .uri(&format!("/api/v1/product/{id}"))
+        .set_json(json!({
+            "name": "Escudo",
+            "description": null,
+            "price": 2,
+            "is_featured": true,
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/product/{id}"))
+        .to_request();
+    let product: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(
+        product,
+        json!({
+            "id": id,
+            "name": "Escudo",
+            "description": null,
+            "price": 2,
+            "is_featured": true,
+            "status": "published",
+            "publish_at": null,
+            "unpublish_at": null,
+        })
+    );
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn patches_only_given_fields(db_pool: TestPool) {
+    let id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/product/{id}"))
+        .set_json(json!({ "price": 12 }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/product/{id}"))
+        .to_request();
+    let product: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(product["name"], "Elmo");
+    assert_eq!(product["description"], "Descrição de Elmo");
+    assert_eq!(product["price"], 12);
+
+    // An explicit null clears the description, unlike a missing field
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/product/{id}"))
+        .set_json(json!({ "description": null, "is_featured": true }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/product/{id}"))
+        .to_request();
+    let product: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(product["description"], Value::Null);
+    assert_eq!(product["is_featured"], true);
+    assert_eq!(product["price"], 12);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn deletes_product(db_pool: TestPool) {
+    let id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::delete()
+        .uri(&format!("/api/v1/product/{id}"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/product/{id}"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn deleted_product_is_hidden_until_restored(db_pool: TestPool) {
+    let id = insert_product(&db_pool, "Elmo", 10, true).await;
+    let authorization = insert_admin(&db_pool).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::delete()
+        .uri(&format!("/api/v1/product/{id}"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for uri in [
+        "/api/v1/product",
+        "/api/v1/product/featured",
+        "/api/v1/product/search/Elmo",
+    ] {
+        let request = test::TestRequest::get().uri(uri).to_request();
+        let products: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+        assert!(products.is_empty(), "{uri} lists a deleted product");
+    }
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/api/v1/admin/product/{id}/restore"))
+        .insert_header(authorization.clone())
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/product/{id}"))
+        .to_request();
+    let product: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(product["name"], "Elmo");
+
+    // Only deleted products can be restored
+    let request = test::TestRequest::post()
+        .uri(&format!("/api/v1/admin/product/{id}/restore"))
+        .insert_header(authorization)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn new_product_is_draft_until_published(db_pool: TestPool) {
+    let authorization = insert_admin(&db_pool).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/product")
+        .set_json(json!({
+            "name": "Elmo",
+            "description": null,
+            "price": 10,
+            "is_featured": true,
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for uri in [
+        "/api/v1/product",
+        "/api/v1/product/featured",
+        "/api/v1/product/search/Elmo",
+        "/api/v1/admin/product?status=published",
+    ] {
+        let request = test::TestRequest::get()
+            .uri(uri)
+            .insert_header(authorization.clone())
+            .to_request();
+        let products: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+        assert!(products.is_empty(), "{uri} lists a draft");
+    }
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/admin/product?status=draft")
+        .insert_header(authorization.clone())
+        .to_request();
+    let products: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(products.len(), 1);
+    let id = products[0]["id"].as_i64().unwrap();
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/product/{id}"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/admin/product/{id}"))
+        .insert_header(authorization.clone())
+        .to_request();
+    let product: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(product["status"], "draft");
+
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/product/{id}"))
+        .set_json(json!({ "status": "published" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get().uri("/api/v1/product").to_request();
+    let products: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(products.len(), 1);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/product/{id}"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/admin/product?status=unknown")
+        .insert_header(authorization.clone())
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn scheduler_publishes_and_archives_due_products(db_pool: TestPool) {
+    let scheduled_id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let expiring_id = insert_product(&db_pool, "Escudo", 20, false).await;
+    let authorization = insert_admin(&db_pool).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    for (id, patch) in [
+        (
+            scheduled_id,
+            json!({ "status": "draft", "publish_at": "2000-01-01T00:00:00" }),
+        ),
+        (
+            expiring_id,
+            json!({ "unpublish_at": "2000-01-01T00:00:00" }),
+        ),
+    ] {
+        let request = test::TestRequest::patch()
+            .uri(&format!("/api/v1/product/{id}"))
+            .set_json(patch)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    iconery_api::product::apply_schedules(&context.data)
+        .await
+        .unwrap();
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/admin/product")
+        .insert_header(authorization)
+        .to_request();
+    let products: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    let statuses: Vec<_> = products
+        .iter()
+        .map(|product| (product["id"].as_i64().unwrap(), product["status"].clone()))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            (scheduled_id, json!("published")),
+            (expiring_id, json!("archived")),
+        ]
+    );
+    // Applied schedules are cleared
+    assert!(
+        products
+            .iter()
+            .all(|product| product["publish_at"].is_null() && product["unpublish_at"].is_null())
+    );
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn scheduler_only_publishes_drafts(db_pool: TestPool) {
+    let id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let authorization = insert_admin(&db_pool).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::patch()
+        .uri(&format!("/api/v1/product/{id}"))
+        .set_json(json!({ "status": "archived", "publish_at": "2000-01-01T00:00:00" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    iconery_api::product::apply_schedules(&context.data)
+        .await
+        .unwrap();
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/admin/product/{id}"))
+        .insert_header(authorization)
+        .to_request();
+    let product: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(product["status"], "archived");
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn lists_featured_products(db_pool: TestPool) {
+    let featured_id = insert_product(&db_pool, "Baú Aberto", 10, true).await;
+    insert_product(&db_pool, "Chave", 9, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/product/featured")
+        .to_request();
+    let products: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(products.len(), 1);
+    assert_eq!(products[0]["id"], featured_id);
+    assert_eq!(products[0]["is_featured"], true);
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn searches_products_by_name(db_pool: TestPool) {
+    insert_product(&db_pool, "Escudo", 2, false).await;
+    insert_product(&db_pool, "Elmo", 10, false).await;
+    insert_product(&db_pool, "Bússola", 1, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/product/search/Escudo")
+        .to_request();
+    let products: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(products.len(), 1);
+    assert_eq!(products[0]["name"], "Escudo");
+
+    // The term is a LIKE pattern
+    let request = test::TestRequest::get()
+        .uri("/api/v1/product/search/E___")
+        .to_request();
+    let products: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(products.len(), 1);
+    assert_eq!(products[0]["name"], "Elmo");
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/product/search/Chave")
+        .to_request();
+    let products: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert!(products.is_empty());
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn legacy_paths_are_deprecated_aliases(db_pool: TestPool) {
+    insert_product(&db_pool, "Elmo", 10, false).await;
+    let context = TestContext::new(db_pool);
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::get().uri("/api/product").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("deprecation").unwrap(), "true");
+    assert_eq!(
+        response.headers().get("link").unwrap(),
+        "</api/v1/product>; rel=\"successor-version\""
+    );
+
+    let products: Vec<Value> = test::read_body_json(response).await;
+    assert_eq!(products.len(), 1);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::{Value, json};
+
+use common::{TestContext, TestPool, insert_admin, insert_customer, insert_product};
+
+/// Moves the deletion of a row far enough into the past to be purged.
+async fn backdate_deletion(db_pool: &TestPool, table: &str, id: i64) {
+    sqlx::query(&format!(
+        "UPDATE {table} SET deleted_at='2000-01-01 00:00:00' WHERE id={id}"
+    ))
+    .execute(db_pool)
+    .await
+    .unwrap();
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn purge_removes_deleted_rows_past_retention(db_pool: TestPool) {
+    let customer_id =
+        insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let ordered_id = insert_product(&db_pool, "Elmo", 10, false).await;
+    let unordered_id = insert_product(&db_pool, "Escudo", 20, false).await;
+    let recent_id = insert_product(&db_pool, "Espada", 30, false).await;
+    let authorization = insert_admin(&db_pool).await;
+    let context = TestContext::new(db_pool.clone());
+    let app = test::init_service(iconery_api::build_app(context.data.clone())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/api/v1/order")
+        .set_json(json!({
+            "customer_id": customer_id,
+            "items": [{ "product_id": ordered_id, "amount": 1 }],
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for id in [ordered_id, unordered_id, recent_id] {
+        let request = test::TestRequest::delete()
+            .uri(&format!("/api/v1/product/{id}"))
+            .to_request();
+        test::call_service(&app, request).await;
+    }
+    backdate_deletion(&db_pool, "products", ordered_id).await;
+    backdate_deletion(&db_pool, "products", unordered_id).await;
+
+    // Products still in an order are kept
+    let request = test::TestRequest::post()
+        .uri("/api/v1/admin/purge")
+        .insert_header(authorization.clone())
+        .to_request();
+    let purged: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(purged, json!({ "customers": 0, "products": 1 }));
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/api/v1/admin/product/{unordered_id}/restore"))
+        .insert_header(authorization.clone())
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let request = test::TestRequest::delete()
+        .uri(&format!("/api/v1/customer/{customer_id}"))
+        .to_request();
+    test::call_service(&app, request).await;
+    backdate_deletion(&db_pool, "customers", customer_id).await;
+
+    // The customer's orders go with them, freeing the ordered product
+    let request = test::TestRequest::post()
+        .uri("/api/v1/admin/purge")
+        .insert_header(authorization.clone())
+        .to_request();
+    let purged: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(purged, json!({ "customers": 1, "products": 1 }));
+
+    let request = test::TestRequest::get().uri("/api/v1/order").to_request();
+    let orders: Vec<Value> = test::call_and_read_body_json(&app, request).await;
+    assert!(orders.is_empty());
+
+    // Deleted too recently to be purged
+    let request = test::TestRequest::post()
+        .uri(&format!("/api/v1/admin/product/{recent_id}/restore"))
+        .insert_header(authorization)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,176 @@
+//! Business rules exercised straight on a database connection, without going through HTTP.
+mod common;
+
+use iconery_api::{
+    customer::{CustomerPatchRequest, CustomerRequest},
+    error::Error,
+    order::{OrderItemRequest, OrderRequest},
+    repository::{CustomerRepo, OrderRepo},
+    seed::SeedProfile,
+    service::{self, customer::SignUp},
+};
+
+use common::{TestContext, TestPool, insert_customer, insert_product};
+
+fn customer_request(name: &str, email: &str, password: &str) -> CustomerRequest {
+    CustomerRequest {
+        name: name.to_owned(),
+        email: email.to_owned(),
+        password: password.to_owned(),
+        phone_number: None,
+        address: None,
+        is_active: None,
+        preferred_locale: None,
+    }
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn sign_up_resends_activation_to_pending_account(db_pool: TestPool) {
+    let context = TestContext::new(db_pool.clone());
+    let mut connection = db_pool.acquire().await.unwrap();
+    let request = customer_request("Maria", "maria@example.com", "senha_maria");
+
+    let sign_up = service::customer::sign_up(&mut *connection, &context.data, &request)
+        .await
+        .unwrap();
+    let SignUp::Created(id) = sign_up else {
+        panic!("the customer should be created");
+    };
+
+    let customer = connection.get_customer(id).await.unwrap();
+    assert!(!customer.is_active);
+    assert_eq!(customer.password, iconery_api::util::hash("senha_maria"));
+
+    let request = customer_request("Outra Maria", "maria@example.com", "outra_senha");
+    let sign_up = service::customer::sign_up(&mut *connection, &context.data, &request)
+        .await
+        .unwrap();
+    assert!(matches!(sign_up, SignUp::ActivationResent));
+    assert_eq!(connection.get_customer(id).await.unwrap().name, "Maria");
+
+    let emails = context.deliver_emails().await;
+    assert_eq!(emails.len(), 2);
+    assert!(emails.iter().all(|email| email.to == "maria@example.com"));
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn sign_up_with_active_email_conflicts(db_pool: TestPool) {
+    insert_customer(&db_pool, "Lucas", "lucas@example.com", "senha_lucas", true).await;
+    let context = TestContext::new(db_pool.clone());
+    let mut connection = db_pool.acquire().await.unwrap();
+
+    let request = customer_request("Lucas", "lucas@example.com", "outra_senha");
+    let result = service::customer::sign_up(&mut *connection, &context.data, &request).await;
+    assert!(matches!(result, Err(Error::ConflictError(_))));
+    assert!(context.deliver_emails().await.is_empty());
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn change_password_checks_current_one(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let mut connection = db_pool.acquire().await.unwrap();
+
+    let changed = service::customer::change_password(&mut *connection, id, "errada", "nova_senha")
+        .await
+        .unwrap();
+    assert!(!changed);
+
+    let changed =
+        service::customer::change_password(&mut *connection, id, "senha_maria", "nova_senha")
+            .await
+            .unwrap();
+    assert!(changed);
+
+    let customer = service::customer::log_in(&mut *connection, "maria@example.com", "nova_senha")
+        .await
+        .unwrap();
+    assert_eq!(customer.map(|customer| customer.id), Some(id));
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn patch_is_rolled_back_with_conflicting_email(db_pool: TestPool) {
+    let id = insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    insert_customer(&db_pool, "Ana", "ana@example.com", "senha_ana", true).await;
+    let context = TestContext::new(db_pool.clone());
+
+    let patch = CustomerPatchRequest {
+        name: Some("Maria Silva".to_owned()),
+        email: Some("ana@example.com".to_owned()),
+        phone_number: None,
+        address: None,
+        is_active: None,
+        preferred_locale: None,
+    };
+    let mut transaction = db_pool.begin().await.unwrap();
+    let result =
+        service::customer::patch_customer(&mut *transaction, &context.data, id, &patch).await;
+    assert!(matches!(result, Err(Error::ConflictError(_))));
+    transaction.rollback().await.unwrap();
+
+    let mut connection = db_pool.acquire().await.unwrap();
+    assert_eq!(connection.get_customer(id).await.unwrap().name, "Maria");
+    assert!(context.deliver_emails().await.is_empty());
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn create_order_totals_items(db_pool: TestPool) {
+    let customer_id =
+        insert_customer(&db_pool, "Maria", "maria@example.com", "senha_maria", true).await;
+    let potion_id = insert_product(&db_pool, "Poção de Vida", 3, true).await;
+    let coins_id = insert_product(&db_pool, "Duas Moedas", 100, false).await;
+    let context = TestContext::new(db_pool.clone());
+    let mut connection = db_pool.acquire().await.unwrap();
+
+    let request = OrderRequest {
+        customer_id,
+        items: vec![
+            OrderItemRequest {
+                product_id: potion_id,
+                amount: 2,
+            },
+            OrderItemRequest {
+                product_id: coins_id,
+                amount: 5,
+            },
+        ],
+    };
+    let order = service::order::create_order(&mut *connection, &context.data, &request)
+        .await
+        .unwrap();
+    assert_eq!(order.total_price, 506);
+
+    let stored = connection.get_order(order.id).await.unwrap();
+    assert_eq!(stored.customer_id, customer_id);
+    assert_eq!(stored.items.len(), 2);
+
+    let emails = context.deliver_emails().await;
+    assert_eq!(emails.len(), 1);
+    assert!(emails[0].text_body.contains("Total: R$506"));
+}
+
+#[sqlx::test(migrator = "common::MIGRATOR")]
+async fn seeding_again_skips_deleted_customers(db_pool: TestPool) {
+    let context = TestContext::new(db_pool.clone());
+    iconery_api::seed::run(&context.data.db_pool, SeedProfile::Test)
+        .await
+        .unwrap();
+
+    let mut connection = db_pool.acquire().await.unwrap();
+    let id = connection
+        .find_customer_id_by_email("active@test.example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    connection.delete_customer(id).await.unwrap();
+
+    iconery_api::seed::run(&context.data.db_pool, SeedProfile::Test)
+        .await
+        .unwrap();
+    assert_eq!(
+        connection
+            .find_customer_id_by_email("active@test.example.com")
+            .await
+            .unwrap(),
+        Some(id)
+    );
+}